@@ -1,3 +1,5 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
 use gloo_net::http::Request;
 use serde::{Deserialize, Serialize};
 
@@ -8,25 +10,154 @@ pub struct Section {
     pub items: Vec<(String, String)>,
 }
 
-pub async fn fetch_toc((lang, version): (String, String)) -> Option<Vec<Section>> {
-    let mut url = String::new();
-    url.push_str("/docs/");
-    url.push_str(&lang);
-    url.push('/');
-    url.push_str(&version);
-    url.push_str("/toc.json");
-    Request::get(&url).send().await.ok()?.json().await.ok()
+/// Title and description of a page, from the `meta.json` written by gen.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Meta {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A page written as `<page>.json` by gen with `--json`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Doc {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The rendered markdown, without the page navigation and the outline.
+    pub html: String,
+    /// The "On this page" outline.
+    #[serde(default)]
+    pub toc: Vec<Heading>,
+    #[serde(default)]
+    pub prev: Option<Link>,
+    #[serde(default)]
+    pub next: Option<Link>,
+    /// `YYYY-MM-DD`, none for the blog listing.
+    #[serde(default)]
+    pub last_updated: Option<String>,
+    /// Path of the markdown source in the repository, none for the blog listing.
+    #[serde(default)]
+    pub source_path: Option<String>,
+    pub labels: Labels,
+}
+
+/// The labels of the page navigation and the outline, from the locale config of gen
+/// as in its html pages. The app locales only label the page metadata.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Labels {
+    pub prev: String,
+    pub next: String,
+    /// The title of the "On this page" outline.
+    pub outline: String,
+}
+
+/// A fetched page, the document of a `--json` build or else the html page, with
+/// its page navigation and its outline.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Content {
+    Doc(Doc),
+    Html(String),
+}
+
+impl Content {
+    pub fn doc(&self) -> Option<&Doc> {
+        match self {
+            Self::Doc(doc) => Some(doc),
+            Self::Html(_) => None,
+        }
+    }
+}
+
+/// A heading of the outline, with the deeper headings following it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+    pub id: String,
+    #[serde(default)]
+    pub children: Vec<Heading>,
+}
+
+/// A link of the page navigation, `link` without leading `/`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    pub title: String,
+    pub link: String,
 }
 
-pub async fn fetch_doc(lang: &str, version: &str, tail: &str) -> Option<String> {
+thread_local! {
+    /// The `manifest.json` of every fetched `<lang>/<version>` directory.
+    static MANIFESTS: RefCell<HashMap<String, Rc<HashMap<String, String>>>> = Default::default();
+}
+
+/// Url of `file` in the docs of `lang` and `version`, through the manifest of the
+/// fingerprinted names of a `--hash` build. Builds without one keep the file names.
+async fn resolve(lang: &str, version: &str, file: &str) -> String {
     let mut url = String::new();
     url.push_str("/docs/");
     url.push_str(lang);
     url.push('/');
     url.push_str(version);
     url.push('/');
-    url.push_str(tail);
-    url.push_str(".html");
+
+    let manifest = match MANIFESTS.with(|manifests| manifests.borrow().get(&url).cloned()) {
+        Some(manifest) => manifest,
+        None => {
+            let manifest = Rc::new(fetch_manifest(&url).await.unwrap_or_default());
+            MANIFESTS
+                .with(|manifests| manifests.borrow_mut().insert(url.clone(), manifest.clone()));
+            manifest
+        }
+    };
+
+    url.push_str(manifest.get(file).map_or(file, String::as_str));
+    url
+}
+
+async fn fetch_manifest(dir: &str) -> Option<HashMap<String, String>> {
+    let req = Request::get(&format!("{dir}manifest.json"))
+        .send()
+        .await
+        .ok()?;
+
+    if !req.ok() {
+        return None;
+    }
+
+    req.json().await.ok()
+}
+
+pub async fn fetch_meta((lang, version): (String, String)) -> Option<HashMap<String, Meta>> {
+    let mut url = String::new();
+    url.push_str("/docs/");
+    url.push_str(&lang);
+    url.push('/');
+    url.push_str(&version);
+    url.push_str("/meta.json");
+    Request::get(&url).send().await.ok()?.json().await.ok()
+}
+
+pub async fn fetch_toc((lang, version): (String, String)) -> Option<Vec<Section>> {
+    let url = resolve(&lang, &version, "toc.json").await;
+    Request::get(&url).send().await.ok()?.json().await.ok()
+}
+
+pub async fn fetch_doc(lang: &str, version: &str, tail: &str) -> Option<Content> {
+    let url = resolve(lang, version, &format!("{tail}.json")).await;
+    let req = Request::get(&url).send().await.ok()?;
+
+    // Builds without `--json` only have the html pages, and unknown files get the
+    // `index.html` of the app, which is not a document.
+    if req.ok() {
+        if let Ok(doc) = req.json().await {
+            return Some(Content::Doc(doc));
+        }
+    }
+
+    let url = resolve(lang, version, &format!("{tail}.html")).await;
     let req = Request::get(&url).send().await.ok()?;
 
     if !req.ok() {
@@ -37,4 +168,5 @@ pub async fn fetch_doc(lang: &str, version: &str, tail: &str) -> Option<String>
         .await
         .ok()
         .filter(|body| body.starts_with("<article"))
+        .map(Content::Html)
 }
@@ -16,6 +16,7 @@ pub fn Navbar() -> impl IntoView {
         home,
         dark,
         sidebar,
+        blog,
     } = expect_context();
     let navigate = store_value(use_navigate());
     let location = use_location();
@@ -29,7 +30,7 @@ pub fn Navbar() -> impl IntoView {
             .and_then(|el| el.get_attribute("data-version"))
             .filter(|ver| ver != &current_version)
             .map(|ver| {
-                if home.get() {
+                if home.get() || blog.get() {
                     log::debug!("version: {}", &ver);
                     version.update(|v| *v = ver.clone());
                 } else {
@@ -103,6 +104,7 @@ pub fn Navbar() -> impl IntoView {
         log::debug!("home: {} {} {:?}", root, &path, location_pathname());
 
         home.update(move |v| *v = root);
+        blog.set(path.split('/').nth(2) == Some("blog"));
     });
 
     view! {
@@ -141,6 +143,9 @@ pub fn Navbar() -> impl IntoView {
                 <A class="transition-colors op75 hover:op100" href=move || format!("/{}/{}/guide/introduction", i18n.get_locale().as_str(), version.get())>
                     <span class=move || if home.get() { "i-lucide-book block" } else { "i-lucide-book-open block" } />
                 </A>
+                <A class="transition-colors op75 hover:op100" href=move || format!("/{}/blog", i18n.get_locale().as_str())>
+                    <span class="i-lucide-newspaper block" />
+                </A>
                 <a rel="noreferrer" target="_blank" class="transition-colors op75 hover:op100" href=move || format!("https://docs.rs/viz/{}", version.get())>
                     <span class="i-lucide-boxes block" />
                 </a>
@@ -178,7 +183,7 @@ pub fn Navbar() -> impl IntoView {
             <button
                 id="toggle-sidebar"
                 class="absolute w-8 h-8 items-center justify-center left-0 bottom--8 transition-colors op75 hover:op100"
-                class=("!hidden", move || home.get())
+                class=("!hidden", move || home.get() || blog.get())
                 on:pointerdown=on_switch_sidebar>
                 <span class="block" class=toggle_class_sidebar />
             </button>
@@ -13,10 +13,11 @@ pub fn Layout() -> impl IntoView {
         dark,
         home,
         sidebar,
+        blog,
         ..
     } = expect_context();
     let dark_matched = RwSignal::new(false);
-    let opened = create_memo(move |_| !home.get() && sidebar.get());
+    let opened = create_memo(move |_| !home.get() && !blog.get() && sidebar.get());
 
     {
         let min_width_media_query =
@@ -63,13 +64,15 @@ pub fn Layout() -> impl IntoView {
                 <div class="page-container pt-4.375rem" class:opened=opened>
                     <div id="backdrop" on:pointerdown=move |_| sidebar.update(|v| *v = false) />
 
-                    <Show when=move || !home.get()>
+                    <Show when=move || !home.get() && !blog.get()>
                         <Sidebar />
                     </Show>
 
                     <main id="page" class="flex flex-row flex-1 py-5">
                         <Routes>
                             <Route path="/" view=Home />
+                            <Route path=":lang/blog" view=Document />
+                            <Route path=":lang/blog/*tail" view=Document />
                             <Route path=":lang/:version/*tail" view=Document />
                             <Route
                                 path="*any"
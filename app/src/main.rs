@@ -8,5 +8,13 @@ pub fn main() {
         log::Level::Info
     });
     console_error_panic_hook::set_once();
+    // The markup of a prerendered page, replaced by the app.
+    if let Some(prerender) = utils::document().get_element_by_id("prerender") {
+        prerender.remove();
+    }
+    // The static description of `index.html`, the pages set their own with `Meta`.
+    if let Ok(Some(meta)) = utils::document().query_selector("meta[name=description]") {
+        meta.remove();
+    }
     mount_to_body(|| view! { <App /> })
 }
@@ -4,29 +4,65 @@ use leptos::*;
 use leptos_dom::helpers::location_hash;
 use leptos_dom::{html::Div, IntoView};
 use leptos_i18n::Locale;
+use leptos_meta::{Link, Meta, Title};
 use leptos_router::use_params;
 use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::{Element, HtmlAnchorElement, HtmlElement};
 
-use crate::api::fetch_doc;
-use crate::i18n::{self, use_i18n};
+use crate::api::{self, fetch_doc, fetch_meta, Content, Doc, Heading, Labels};
+use crate::i18n::{self, t, use_i18n};
 use crate::pages::{ComingSoon, NotFound};
 use crate::{
     langs_contains,
-    utils::{copy, document, document_element, set_timeout},
-    versions_contains, UNPUBLISHED, VERSIONS,
+    utils::{copy, document, document_element, local_storage, set_timeout},
+    versions_contains, DESCRIPTION, EDIT_URL, SITE, SITE_URL, TITLE, UNPUBLISHED, VERSIONS,
 };
 use crate::{DocumentParams, GlobalState};
 
 #[component]
 pub fn Document() -> impl IntoView {
-    let GlobalState { version, .. } = expect_context();
+    let GlobalState { version, blog, .. } = expect_context();
     let current_params = use_params::<DocumentParams>();
     let container = create_node_ref::<Div>();
     let disable = RwSignal::new(false);
     let loading = RwSignal::new(false);
     let i18n = use_i18n();
 
+    let metas = create_resource(
+        move || {
+            let params = current_params.get().unwrap_or_default();
+            (
+                params.lang.unwrap_or_default(),
+                params.version.unwrap_or_else(|| "blog".to_string()),
+            )
+        },
+        fetch_meta,
+    );
+    // Locale, url path and meta of the current page, which pages without an entry
+    // in `meta.json` lack along with their Open Graph image.
+    let page = create_memo(move |_| {
+        let params = current_params.get().unwrap_or_default();
+        let lang = params.lang.unwrap_or_default();
+        let tail = params.tail.filter(|tail| !tail.is_empty());
+        let path = match (&params.version, &tail) {
+            (Some(version), tail) => {
+                format!("/{lang}/{version}/{}", tail.as_deref().unwrap_or_default())
+            }
+            (None, Some(tail)) => format!("/{lang}/blog/{tail}"),
+            (None, None) => format!("/{lang}/blog"),
+        };
+        let meta = metas
+            .get()
+            .flatten()
+            .and_then(|metas| metas.get(tail.as_deref().unwrap_or("index")).cloned());
+        (lang, path, meta)
+    });
+    let url = move || page.with(|(_, path, _)| format!("{SITE_URL}{path}"));
+    let locale = move || page.with(|(lang, _, _)| lang.replace('-', "_"));
+    let image = move || {
+        page.with(|(_, path, meta)| meta.as_ref().map(|_| format!("{SITE_URL}/docs{path}.png")))
+    };
+
     let click = move |e: ev::PointerEvent| {
         if let Some(target) = e
             .target()
@@ -43,7 +79,7 @@ pub fn Document() -> impl IntoView {
                     .and_then(|node| node.dyn_into::<HtmlElement>().ok())
                 {
                     wasm_bindgen_futures::spawn_local(async move {
-                        copy(&next.inner_text()).await;
+                        copy(&code_text(&next)).await;
                         let _ = target.class_list().add_1("text-lime-500");
                         let _ = target.class_list().remove_1("op-20");
                         set_timeout(
@@ -55,6 +91,13 @@ pub fn Document() -> impl IntoView {
                         );
                     });
                 }
+            } else if target.matches(".code-group .tab").unwrap_or(false) {
+                e.stop_immediate_propagation();
+
+                if let Some(tab) = target.get_attribute("data-tab") {
+                    local_storage::set_code_tab(&tab);
+                    select_tab(container, &tab);
+                }
             } else if target.matches("a.toc-link").unwrap_or(false) {
                 e.stop_immediate_propagation();
 
@@ -85,6 +128,16 @@ pub fn Document() -> impl IntoView {
                 version: ver,
             } = input?;
             let l = lang.filter(|v| langs_contains(&v.as_str()))?;
+
+            // The blog routes have no version, its listing is `blog/index`.
+            if ver.is_none() {
+                let t = tail
+                    .filter(|v| !v.is_empty())
+                    .unwrap_or_else(|| "index".to_string());
+                i18n.set_locale(i18n::Locale::from_str(&l)?);
+                return fetch_doc(&l, "blog", &t).await;
+            }
+
             let v = ver.filter(|v| versions_contains(&v.as_str()))?;
             let t = tail.filter(|v| !v.is_empty())?;
 
@@ -103,6 +156,35 @@ pub fn Document() -> impl IntoView {
         },
     );
 
+    // The document's own title and description, then those of `meta.json`
+    // while it loads.
+    let title = move || {
+        resource
+            .with(|content| {
+                content
+                    .as_ref()
+                    .and_then(Content::doc)
+                    .and_then(|doc| doc.title.clone())
+            })
+            .flatten()
+            .or_else(|| page.with(|(_, _, meta)| meta.as_ref().and_then(|meta| meta.title.clone())))
+            .map_or_else(|| TITLE.to_string(), |title| format!("{title} | {SITE}"))
+    };
+    let description = move || {
+        resource
+            .with(|content| {
+                content
+                    .as_ref()
+                    .and_then(Content::doc)
+                    .and_then(|doc| doc.description.clone())
+            })
+            .flatten()
+            .or_else(|| {
+                page.with(|(_, _, meta)| meta.as_ref().and_then(|meta| meta.description.clone()))
+            })
+            .unwrap_or_else(|| DESCRIPTION.to_string())
+    };
+
     create_effect(move |_| {
         loading.set(false);
 
@@ -112,36 +194,30 @@ pub fn Document() -> impl IntoView {
         root.set_inner_html("");
         mount_to(root.clone(), move || match resource {
             None => {
-                if VERSIONS[UNPUBLISHED] == version.get() {
+                if !blog.get() && VERSIONS[UNPUBLISHED] == version.get() {
                     ComingSoon().into_view()
                 } else {
                     NotFound().into_view()
                 }
             }
-            Some(content) => {
-                view! { <div class="flex flex-row flex-1" inner_html=content /> }.into_view()
+            Some(Content::Doc(doc)) => view! { <Article doc /> }.into_view(),
+            Some(Content::Html(html)) => {
+                view! { <div class="flex flex-row flex-1" inner_html=html /> }.into_view()
             }
         });
 
+        if let Some(tab) = local_storage::get_code_tab() {
+            select_tab(container, &tab);
+        }
+
         let hash = location_hash();
         let hashtag = hash.as_ref();
 
-        let article = root.query_selector("article").ok()??;
-        let nodes = article.query_selector_all("h2").ok()?;
-        let mut found = None;
-
-        for idx in 0..nodes.length() {
-            if found.is_some() {
-                break;
-            }
-            nodes
-                .get(idx)
-                .as_ref()
-                .and_then(JsCast::dyn_ref::<HtmlElement>)
-                .map(|node| found = hashtag.filter(|h| **h == node.id()).map(|_| idx));
-        }
-
-        let idx = found.unwrap_or(0);
+        let nodes = headings(container);
+        let idx = nodes
+            .iter()
+            .position(|node| hashtag.filter(|h| **h == node.id()).is_some())
+            .unwrap_or(0);
 
         log::debug!("idx: {}", idx);
 
@@ -149,7 +225,6 @@ pub fn Document() -> impl IntoView {
 
         nodes
             .get(idx)
-            .as_ref()
             .and_then(JsCast::dyn_ref::<HtmlElement>)
             .map(|node| {
                 if idx == 0 {
@@ -168,6 +243,23 @@ pub fn Document() -> impl IntoView {
     on_cleanup(move || drop(listener));
 
     view! {
+        <Title text=title />
+        <Meta name="description" content=description />
+        <Meta property="og:type" content="article" />
+        <Meta property="og:site_name" content=SITE />
+        <Meta property="og:title" content=title />
+        <Meta property="og:description" content=description />
+        <Meta property="og:url" content=url />
+        <Meta property="og:locale" content=locale />
+        <Meta
+            name="twitter:card"
+            content=move || if image().is_some() { "summary_large_image" } else { "summary" }
+        />
+        <Meta name="twitter:title" content=title />
+        <Meta name="twitter:description" content=description />
+        {move || view! { <Link rel="canonical" href=url() /> }}
+        {move || image().map(|image| view! { <Meta property="og:image" content=image /> })}
+
         <div class="flex flex-row flex-1">
             // <Suspense
             //     fallback=|| view! {
@@ -193,6 +285,125 @@ pub fn Document() -> impl IntoView {
     }
 }
 
+/// A document with its metadata, its page navigation and its outline, in the
+/// markup of the html pages of gen.
+#[component]
+fn Article(doc: Doc) -> impl IntoView {
+    let i18n = use_i18n();
+    let Doc {
+        html,
+        toc,
+        prev,
+        next,
+        last_updated,
+        source_path,
+        labels,
+        ..
+    } = doc;
+    let title = labels.outline.clone();
+
+    view! {
+        <div class="flex flex-row flex-1">
+            <article class="flex-1">
+                <div inner_html=html />
+                {(last_updated.is_some() || source_path.is_some()).then(|| view! {
+                    <div class="page-meta">
+                        <span>
+                            {last_updated.map(|date| view! {
+                                {t!(i18n, last_updated)}": "
+                                <time datetime=date.clone()>{date}</time>
+                            })}
+                        </span>
+                        {source_path.map(|path| view! {
+                            <a
+                                rel="noreferrer"
+                                target="_blank"
+                                class="transition-colors hover:op100 op61.8"
+                                href=format!("{EDIT_URL}/{path}")
+                            >
+                                <i class="inline-block i-lucide-pencil w-3 h-3"></i>
+                                " "{t!(i18n, edit_page)}
+                            </a>
+                        })}
+                    </div>
+                })}
+                <PageNav prev next labels />
+            </article>
+            <Outline toc title />
+        </div>
+    }
+}
+
+/// The links to the previous and the next pages, at the end of the article.
+#[component]
+fn PageNav(prev: Option<api::Link>, next: Option<api::Link>, labels: Labels) -> impl IntoView {
+    (prev.is_some() || next.is_some()).then(|| {
+        view! {
+            <div class="page-nav">
+                {match prev {
+                    Some(api::Link { title, link }) => view! {
+                        <a class="prev-link transition-colors hover:op100 op61.8" href=format!("/{link}")>
+                            <span class="desc">
+                                <i class="block i-lucide-chevron-left w-3 h-3"></i>
+                                " "{labels.prev}
+                            </span>
+                            <span class="title">{title}</span>
+                        </a>
+                    }
+                    .into_view(),
+                    None => view! { <div class="prev-link"></div> }.into_view(),
+                }}
+                {match next {
+                    Some(api::Link { title, link }) => view! {
+                        <a class="next-link transition-colors hover:op100 op61.8" href=format!("/{link}")>
+                            <span class="desc">
+                                {labels.next}" "
+                                <i class="block i-lucide-chevron-right w-3 h-3"></i>
+                            </span>
+                            <span class="title">{title}</span>
+                        </a>
+                    }
+                    .into_view(),
+                    None => view! { <div class="next-link"></div> }.into_view(),
+                }}
+            </div>
+        }
+    })
+}
+
+/// The "On this page" outline next to the article, followed by the scroll.
+#[component]
+fn Outline(toc: Vec<Heading>, title: String) -> impl IntoView {
+    (!toc.is_empty()).then(|| {
+        view! {
+            <nav class="flex-col gap-5 hidden lg:flex">
+                <div class="py-1 text-2 uppercase">{title}</div>
+                <ul class="text-3">{items(toc)}</ul>
+            </nav>
+        }
+    })
+}
+
+/// The entries of the outline, deeper headings nested under the previous one.
+fn items(headings: Vec<Heading>) -> View {
+    headings
+        .into_iter()
+        .map(|heading| {
+            view! {
+                <li>
+                    <a
+                        class="toc-link block py-1 font-normal transition-colors op75 hover:op100"
+                        href=format!("#{}", heading.id)
+                    >
+                        {heading.text}
+                    </a>
+                    {(!heading.children.is_empty()).then(|| view! { <ul>{items(heading.children)}</ul> })}
+                </li>
+            }
+        })
+        .collect_view()
+}
+
 #[inline]
 fn update_ul_style(
     container: NodeRef<Div>,
@@ -202,7 +413,7 @@ fn update_ul_style(
     let ul = container
         .get_untracked()
         .ok_or(JsValue::NULL)?
-        .query_selector("article + nav ul")?
+        .query_selector("article + nav > ul")?
         .and_then(|node| node.dyn_into::<HtmlElement>().ok())
         .ok_or(JsValue::NULL)?;
 
@@ -231,24 +442,100 @@ fn update_ul_style(
 
 #[inline]
 fn scroll(container: NodeRef<Div>) -> Option<String> {
-    let root = container.get_untracked()?;
-    let article = root.query_selector("article").ok()??;
-    let nodes = article.query_selector_all("h2").ok()?;
-
     let mut id = None;
 
-    for idx in 0..nodes.length() {
-        if let Some(e) = nodes
+    for e in headings(container) {
+        let rect = e.get_bounding_client_rect();
+        if rect.top() - 106. > 0. {
+            break;
+        }
+        id.replace(e.id());
+    }
+
+    id
+}
+
+/// The headings listed in the outline, nested H3/H4 entries included, in page order.
+fn headings(container: NodeRef<Div>) -> Vec<Element> {
+    let Some(root) = container.get_untracked() else {
+        return Vec::new();
+    };
+    let Ok(links) = root.query_selector_all("article + nav a.toc-link") else {
+        return Vec::new();
+    };
+
+    (0..links.length())
+        .filter_map(|idx| links.get(idx))
+        .filter_map(|node| node.dyn_into::<Element>().ok())
+        .filter_map(|a| a.get_attribute("href"))
+        .filter_map(|href| {
+            href.strip_prefix('#')
+                .and_then(|id| document().get_element_by_id(id))
+        })
+        .collect()
+}
+
+/// The text of a code block, without the line numbers.
+fn code_text(pre: &HtmlElement) -> String {
+    let Some(pre) = pre
+        .clone_node_with_deep(true)
+        .ok()
+        .and_then(|node| node.dyn_into::<Element>().ok())
+    else {
+        return pre.inner_text();
+    };
+
+    if let Ok(nodes) = pre.query_selector_all(".line-no") {
+        for idx in 0..nodes.length() {
+            if let Some(e) = nodes
+                .get(idx)
+                .and_then(|node| node.dyn_into::<Element>().ok())
+            {
+                e.remove();
+            }
+        }
+    }
+
+    pre.text_content().unwrap_or_default()
+}
+
+/// Shows the `tab` of every code group that has it.
+fn select_tab(container: NodeRef<Div>, tab: &str) {
+    let Some(root) = container.get_untracked() else {
+        return;
+    };
+    let Ok(groups) = root.query_selector_all(".code-group") else {
+        return;
+    };
+
+    for idx in 0..groups.length() {
+        let Some(group) = groups
             .get(idx)
             .and_then(|node| node.dyn_into::<Element>().ok())
+        else {
+            continue;
+        };
+        let Ok(items) = group.query_selector_all(".tab, .tab-panel") else {
+            continue;
+        };
+        let items = (0..items.length())
+            .filter_map(|idx| items.get(idx))
+            .filter_map(|node| node.dyn_into::<Element>().ok())
+            .collect::<Vec<_>>();
+
+        if !items
+            .iter()
+            .any(|e| e.get_attribute("data-tab").as_deref() == Some(tab))
         {
-            let rect = e.get_bounding_client_rect();
-            if rect.top() - 106. > 0. {
-                break;
-            }
-            id.replace(e.id());
+            continue;
         }
-    }
 
-    id
+        let _ = group.class_list().add_1("selected");
+        for e in items {
+            let _ = e.class_list().toggle_with_force(
+                "active",
+                e.get_attribute("data-tab").as_deref() == Some(tab),
+            );
+        }
+    }
 }
@@ -7,6 +7,13 @@ pub const LANGS: [[&str; 2]; 3] = [
     ["zh-TW", "繁體中文"],
 ];
 pub const VERSIONS: [&str; 2] = ["0.5.x", "0.4.x"];
+pub const SITE: &str = "Viz.rs";
+pub const SITE_URL: &str = "https://viz.rs";
+/// Prefix of the edit link of a page, followed by the `source_path` of its document.
+pub const EDIT_URL: &str = "https://github.com/viz-rs/viz.rs/edit/main";
+pub const TITLE: &str = "Viz.rs | Fast, robust, flexible, lightweight web framework for Rust";
+/// Description of the home page, and of the pages without their own.
+pub const DESCRIPTION: &str = "Fast, robust, flexible, lightweight web framework for Rust";
 pub const LATEST: usize = 1;
 pub const UNPUBLISHED: usize = 0;
 
@@ -15,6 +22,8 @@ pub struct GlobalState {
     pub dark: RwSignal<bool>,
     pub home: RwSignal<bool>,
     pub sidebar: RwSignal<bool>,
+    /// On a page of the blog, which has no version.
+    pub blog: RwSignal<bool>,
     // pub lang: RwSignal<String>,
     pub version: RwSignal<String>,
 }
@@ -25,6 +34,7 @@ impl GlobalState {
             dark: create_rw_signal(false),
             home: create_rw_signal(true),
             sidebar: create_rw_signal(false),
+            blog: create_rw_signal(false),
             // lang: create_rw_signal(LANGS[0][0].to_string()),
             version: create_rw_signal(VERSIONS[LATEST].to_string()),
         }
@@ -74,4 +74,12 @@ pub mod local_storage {
     pub fn set_color_scheme(val: &str) -> Option<()> {
         set("color-scheme", val)
     }
+
+    pub fn get_code_tab() -> Option<String> {
+        get("code-tab")
+    }
+
+    pub fn set_code_tab(val: &str) -> Option<()> {
+        set("code-tab", val)
+    }
 }
@@ -0,0 +1,102 @@
+(comment) @comment
+(marginalia) @comment
+
+(literal) @string
+
+(invocation
+  (object_reference
+    name: (identifier) @function))
+
+(relation
+  alias: (identifier) @variable)
+
+(field
+  name: (identifier) @field)
+
+(object_reference
+  name: (identifier) @type)
+
+[
+  (keyword_null)
+  (keyword_true)
+  (keyword_false)
+] @constant
+
+[
+  (keyword_select)
+  (keyword_from)
+  (keyword_where)
+  (keyword_insert)
+  (keyword_into)
+  (keyword_values)
+  (keyword_update)
+  (keyword_set)
+  (keyword_delete)
+  (keyword_create)
+  (keyword_table)
+  (keyword_drop)
+  (keyword_alter)
+  (keyword_index)
+  (keyword_on)
+  (keyword_join)
+  (keyword_left)
+  (keyword_inner)
+  (keyword_order)
+  (keyword_group)
+  (keyword_by)
+  (keyword_having)
+  (keyword_limit)
+  (keyword_offset)
+  (keyword_as)
+  (keyword_distinct)
+  (keyword_returning)
+  (keyword_primary)
+  (keyword_key)
+  (keyword_references)
+  (keyword_default)
+  (keyword_if)
+  (keyword_exists)
+] @keyword
+
+[
+  (keyword_and)
+  (keyword_or)
+  (keyword_not)
+  (keyword_in)
+  (keyword_is)
+  (keyword_like)
+] @operator
+
+[
+  (keyword_int)
+  (keyword_bigint)
+  (keyword_boolean)
+  (keyword_text)
+  (keyword_varchar)
+  (keyword_timestamp)
+] @type
+
+[
+  "+"
+  "-"
+  "*"
+  "/"
+  "="
+  "<"
+  ">"
+  "<="
+  ">="
+  "!="
+  "<>"
+] @operator
+
+[
+  "("
+  ")"
+] @punctuation.bracket
+
+[
+  ";"
+  ","
+  "."
+] @punctuation.delimiter
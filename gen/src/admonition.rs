@@ -0,0 +1,119 @@
+use pulldown_cmark::{escape::escape_html, CowStr, Event, Tag};
+use serde::Deserialize;
+
+/// Callout labels, the `[admonitions]` table of `gen/locales/<locale>.toml`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Admonitions {
+    pub note: String,
+    pub tip: String,
+    pub warning: String,
+    pub caution: String,
+}
+
+impl Default for Admonitions {
+    fn default() -> Self {
+        Self {
+            note: "Note".to_string(),
+            tip: "Tip".to_string(),
+            warning: "Warning".to_string(),
+            caution: "Caution".to_string(),
+        }
+    }
+}
+
+impl Admonitions {
+    /// Returns the class, icon and label of a `[!KIND]` marker.
+    fn kind(&self, marker: &str) -> Option<(&'static str, &'static str, &str)> {
+        let kind = marker.strip_prefix("[!")?.strip_suffix(']')?;
+        match kind.to_ascii_uppercase().as_str() {
+            "NOTE" => Some(("note", "i-lucide-info", &self.note)),
+            "TIP" => Some(("tip", "i-lucide-lightbulb", &self.tip)),
+            "WARNING" => Some(("warning", "i-lucide-alert-triangle", &self.warning)),
+            "CAUTION" => Some(("caution", "i-lucide-alert-octagon", &self.caution)),
+            _ => None,
+        }
+    }
+}
+
+/// Turns GitHub-style `> [!NOTE]` blockquotes into callouts.
+///
+/// The marker must be alone on the first line of the quote, other blockquotes are
+/// left untouched.
+pub fn rewrite<'a>(events: Vec<Event<'a>>, admonitions: &Admonitions) -> Vec<Event<'a>> {
+    let mut output = Vec::with_capacity(events.len());
+    // Whether each open blockquote was turned into a callout.
+    let mut quotes = Vec::new();
+    let mut i = 0;
+
+    while i < events.len() {
+        match &events[i] {
+            Event::Start(Tag::BlockQuote) => {
+                let Some((skip, keep_paragraph, open)) = marker(&events[i + 1..], admonitions)
+                else {
+                    quotes.push(false);
+                    output.push(events[i].clone());
+                    i += 1;
+                    continue;
+                };
+
+                quotes.push(true);
+                output.push(Event::Html(CowStr::from(open)));
+                if keep_paragraph {
+                    output.push(Event::Start(Tag::Paragraph));
+                }
+                i += 1 + skip;
+            }
+            Event::End(Tag::BlockQuote) => {
+                if quotes.pop() == Some(true) {
+                    output.push(Event::Html(CowStr::Borrowed("</div>")));
+                } else {
+                    output.push(events[i].clone());
+                }
+                i += 1;
+            }
+            event => {
+                output.push(event.clone());
+                i += 1;
+            }
+        }
+    }
+
+    output
+}
+
+/// Looks for a marker paragraph at the start of a blockquote.
+///
+/// Returns the number of events to skip, whether the first paragraph goes on after
+/// the marker line and the opening html of the callout.
+fn marker(events: &[Event], admonitions: &Admonitions) -> Option<(usize, bool, String)> {
+    if !matches!(events.first(), Some(Event::Start(Tag::Paragraph))) {
+        return None;
+    }
+
+    // `[!NOTE]` may be split into several text events by the link parser.
+    let mut text = String::new();
+    let mut n = 1;
+    while let Some(Event::Text(t)) = events.get(n) {
+        text.push_str(t);
+        n += 1;
+    }
+
+    let (class, icon, label) = admonitions.kind(text.trim())?;
+    let keep_paragraph = match events.get(n)? {
+        Event::SoftBreak | Event::HardBreak => true,
+        Event::End(Tag::Paragraph) => false,
+        _ => return None,
+    };
+
+    let mut open = String::new();
+    open.push_str("<div class='callout ");
+    open.push_str(class);
+    open.push_str("'><p class='callout-title'><i class='");
+    open.push_str(icon);
+    open.push_str(" w-4 h-4'></i>");
+    escape_html(&mut open, label).unwrap();
+    open.push_str("</p>");
+
+    Some((n + 1, keep_paragraph, open))
+}
@@ -0,0 +1,519 @@
+use std::{collections::BTreeMap, ffi::OsStr, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use highlighting::Languages;
+use minijinja::context;
+use pulldown_cmark::escape::escape_html;
+use serde::Deserialize;
+
+use crate::{
+    cache,
+    front_matter::{self, FrontMatter},
+    image::{self, Image},
+    json,
+    manifest::Manifest,
+    og::Og,
+    parse, sitemap,
+    templates::Templates,
+    Config, Outputs,
+};
+
+/// Posts listed on a page of the blog.
+const PER_PAGE: usize = 10;
+
+/// Blog labels, the `[blog]` table of `gen/locales/<locale>.toml`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Blog {
+    pub title: String,
+    pub description: String,
+    pub newer: String,
+    pub older: String,
+    /// Shown on the listing while there are no posts.
+    pub empty: String,
+}
+
+impl Default for Blog {
+    fn default() -> Self {
+        Self {
+            title: "Blog".to_string(),
+            description: "Release notes and articles about Viz".to_string(),
+            newer: "Newer posts".to_string(),
+            older: "Older posts".to_string(),
+            empty: "No posts yet.".to_string(),
+        }
+    }
+}
+
+/// A dated markdown file of `<locale>/blog`.
+#[derive(Debug)]
+pub struct Post {
+    pub slug: String,
+    pub meta: FrontMatter,
+    pub date: String,
+    body: String,
+}
+
+/// Posts of the content directory `dir` of a locale, newest first. Drafts are left
+/// out.
+pub fn posts(dir: &Path) -> Result<Vec<Post>> {
+    let root = dir.join("blog");
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut posts = Vec::new();
+    for entry in fs::read_dir(&root)? {
+        let path = entry?.path();
+        if path.extension() != Some(OsStr::new("md")) {
+            continue;
+        }
+
+        let raw = fs::read_to_string(&path)?;
+        let (meta, body) = front_matter::split(&raw)?;
+        if meta.draft {
+            continue;
+        }
+        // Zero-padded only, the posts are sorted by their date as a string.
+        let Some(date) = meta.date.clone().filter(|date| days(date).is_some()) else {
+            bail!("{}: missing or unpadded `date: YYYY-MM-DD`", path.display());
+        };
+
+        let slug = path.file_stem().unwrap().to_string_lossy().to_string();
+        if matches!(slug.as_str(), "index" | "page" | "meta" | "manifest") {
+            bail!("{}: `{slug}` is reserved", path.display());
+        }
+
+        posts.push(Post {
+            slug,
+            date,
+            body: body.to_string(),
+            meta,
+        });
+    }
+    posts.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.slug.cmp(&b.slug)));
+
+    Ok(posts)
+}
+
+/// Renders the posts, the listing pages, `meta.json` and the feeds of
+/// `config.locale` into `<dist>/blog`, returning the number of written files.
+/// Without posts, the listing and the feeds are still written, empty.
+///
+/// The listing is `blog/index.html`, then `blog/page/<n>.html`. Images of the posts
+/// go in `<locale>/blog/images` and are referenced as `../images/<name>`. With
+/// `outputs.hash`, the pages and the images are fingerprinted and mapped in
+/// `manifest.json`. With `outputs.json`, every page has a `.json` document too.
+#[allow(clippy::too_many_arguments)]
+pub fn build(
+    root: &Path,
+    languages: &Languages,
+    templates: &Templates,
+    og: &Og,
+    config: &Config,
+    dist: &Path,
+    base_url: &str,
+    outputs: Outputs,
+) -> Result<usize> {
+    let posts = posts(&config.dir)?;
+    let locale = &config.locale;
+    let base_url = base_url.trim_end_matches('/');
+    let dist = dist.join("blog");
+    fs::create_dir_all(&dist)?;
+
+    let mut minify_cfg = minify_html::Cfg::new();
+    minify_cfg.keep_closing_tags = true;
+    let mut written = 0;
+
+    let link = |post: &Post| {
+        (
+            post.meta.title.clone().unwrap_or_else(|| post.slug.clone()),
+            format!("{locale}/blog/{}", post.slug),
+        )
+    };
+
+    // Images first, the posts are rendered with their fingerprinted names.
+    let mut manifest = outputs.hash.then(Manifest::default);
+    let images = config.dir.join("blog").join("images");
+    if images.is_dir() {
+        fs::create_dir_all(dist.join("images"))?;
+        for entry in fs::read_dir(&images)? {
+            let path = entry?.path();
+            if !matches!(path.extension().and_then(OsStr::to_str), Some(e) if image::EXTENSIONS.contains(&e))
+            {
+                continue;
+            }
+            let raw = fs::read(&path)?;
+            let name = format!("images/{}", path.file_name().unwrap().to_string_lossy());
+            let fp = dist.join(output(&mut manifest, &name, &raw));
+            if let Err(error) = Image::load(&path).and_then(|image| image.write(&raw, &fp)) {
+                eprintln!("warning: {error:#}, copied as is");
+                fs::write(&fp, &raw)?;
+            }
+            println!("{:?}", fp.canonicalize()?);
+            written += 1;
+        }
+    }
+
+    // Posts link to their neighbours with the labels of the listing.
+    let post_config = Config {
+        prev: config.blog.newer.clone(),
+        next: config.blog.older.clone(),
+        ..config.clone()
+    };
+    let mut contents = Vec::with_capacity(posts.len());
+    for (i, post) in posts.iter().enumerate() {
+        let newer = i.checked_sub(1).map(|i| link(&posts[i]));
+        let older = posts.get(i + 1).map(link);
+        let document = parse(
+            &post_config,
+            languages,
+            templates,
+            None,
+            manifest.as_ref(),
+            (newer, older, "blog".to_string()),
+            post.meta.clone(),
+            &post.body,
+        )?;
+
+        let mut time = String::new();
+        time.push_str("<time class='post-date' datetime='");
+        time.push_str(&post.date);
+        time.push_str("'>");
+        time.push_str(&post.date);
+        time.push_str("</time>");
+        let html = document.html.replacen(
+            "<article class='flex-1'>",
+            &format!("<article class='flex-1'>{time}"),
+            1,
+        );
+
+        let minified = minify_html::minify(html.as_bytes(), &minify_cfg);
+        let fp = dist.join(output(
+            &mut manifest,
+            &format!("{}.html", post.slug),
+            &minified,
+        ));
+        fs::write(&fp, minified)?;
+        println!("{:?}", fp.canonicalize()?);
+        if outputs.json {
+            let source = Path::new(locale)
+                .join("blog")
+                .join(&post.slug)
+                .with_extension("md");
+            let body =
+                minify_html::minify(format!("{time}{}", document.body).as_bytes(), &minify_cfg);
+            let body = String::from_utf8_lossy(&body);
+            let last_updated = sitemap::lastmod(&root.join(&source))?;
+            let page = json::Page::new(&post_config, &document, &body, &source, &last_updated);
+            let content = serde_json::to_string(&page)?;
+            let fp = dist.join(output(
+                &mut manifest,
+                &format!("{}.json", post.slug),
+                content.as_bytes(),
+            ));
+            fs::write(&fp, content)?;
+            println!("{:?}", fp.canonicalize()?);
+        } else {
+            // Fingerprinted ones are pruned with the manifest.
+            json::remove(&dist.join(format!("{}.json", post.slug)))?;
+        }
+        fs::write(
+            dist.join(&post.slug).with_extension("png"),
+            og.render(
+                document.meta.title.as_deref().unwrap_or(&post.slug),
+                &config.blog.title,
+                &post.date,
+            )?,
+        )?;
+        written += 1;
+
+        contents.push(document.body);
+    }
+
+    let pages = posts.len().div_ceil(PER_PAGE).max(1);
+    for n in 0..pages {
+        let chunk = &posts[n * PER_PAGE..posts.len().min((n + 1) * PER_PAGE)];
+        let body = listing(config, templates, chunk, n + 1, pages)?;
+        let name = if n == 0 {
+            "index".to_string()
+        } else {
+            fs::create_dir_all(dist.join("page"))?;
+            format!("page/{}", n + 1)
+        };
+        let html = format!("<article class='flex-1'>{body}</article>");
+        let minified = minify_html::minify(html.as_bytes(), &minify_cfg);
+        let fp = dist.join(output(&mut manifest, &format!("{name}.html"), &minified));
+        fs::write(&fp, minified)?;
+        println!("{:?}", fp.canonicalize()?);
+        if outputs.json {
+            let body = minify_html::minify(body.as_bytes(), &minify_cfg);
+            let page = json::Page {
+                title: Some(&config.blog.title),
+                description: Some(&config.blog.description),
+                html: &String::from_utf8_lossy(&body),
+                toc: &[],
+                prev: None,
+                next: None,
+                last_updated: None,
+                source_path: None,
+                labels: json::Labels::from(config),
+            };
+            let content = serde_json::to_string(&page)?;
+            let fp = dist.join(output(
+                &mut manifest,
+                &format!("{name}.json"),
+                content.as_bytes(),
+            ));
+            fs::write(&fp, content)?;
+            println!("{:?}", fp.canonicalize()?);
+        } else {
+            json::remove(&dist.join(format!("{name}.json")))?;
+        }
+        written += 1;
+    }
+
+    let metas = posts
+        .iter()
+        .map(|post| (&post.slug, &post.meta))
+        .collect::<BTreeMap<_, _>>();
+    let fp = dist.join("meta.json");
+    fs::write(&fp, serde_json::to_string(&metas)?)?;
+    println!("{:?}", fp.canonicalize()?);
+
+    let fp = dist.join("atom.xml");
+    fs::write(
+        &fp,
+        atom(
+            config,
+            &posts,
+            &contents,
+            &updated(root, locale, &posts)?,
+            base_url,
+        )?,
+    )?;
+    println!("{:?}", fp.canonicalize()?);
+
+    let fp = dist.join("rss.xml");
+    fs::write(&fp, rss(config, &posts, &contents, base_url)?)?;
+    println!("{:?}", fp.canonicalize()?);
+
+    match &manifest {
+        Some(manifest) => manifest.save(&dist)?,
+        None => Manifest::remove(&dist)?,
+    }
+
+    Ok(written + 2)
+}
+
+/// Date of the last change of the blog of `locale`, that of its newest post or, without
+/// posts, of its labels in `root`.
+pub fn updated(root: &Path, locale: &str, posts: &[Post]) -> Result<String> {
+    match posts.first() {
+        Some(newest) => Ok(newest.date.clone()),
+        None => sitemap::lastmod(&root.join("gen/locales").join(format!("{locale}.toml"))),
+    }
+}
+
+/// Name of the output of `name`, fingerprinted from its `content` with a manifest.
+fn output(manifest: &mut Option<Manifest>, name: &str, content: &[u8]) -> String {
+    match manifest {
+        Some(manifest) => manifest.insert(name, cache::hash(&content)),
+        None => name.to_string(),
+    }
+}
+
+/// Link of the `n`th listing page, without leading `/` like in `page-nav.html`.
+fn page_link(locale: &str, n: usize) -> String {
+    if n == 1 {
+        format!("{locale}/blog")
+    } else {
+        format!("{locale}/blog/page/{n}")
+    }
+}
+
+/// The posts of the `n`th listing page and the links to its neighbours.
+fn listing(
+    config: &Config,
+    templates: &Templates,
+    posts: &[Post],
+    n: usize,
+    pages: usize,
+) -> Result<String> {
+    let locale = &config.locale;
+    let blog = &config.blog;
+
+    let mut html = String::new();
+    html.push_str("<h1>");
+    escape_html(&mut html, &blog.title).unwrap();
+    html.push_str("</h1><ul class='posts'>");
+    for post in posts {
+        html.push_str("<li><a href='/");
+        html.push_str(locale);
+        html.push_str("/blog/");
+        escape_html(&mut html, &post.slug).unwrap();
+        html.push_str("'><time class='post-date' datetime='");
+        html.push_str(&post.date);
+        html.push_str("'>");
+        html.push_str(&post.date);
+        html.push_str("</time><h2>");
+        escape_html(&mut html, post.meta.title.as_deref().unwrap_or(&post.slug)).unwrap();
+        html.push_str("</h2>");
+        if let Some(description) = &post.meta.description {
+            html.push_str("<p>");
+            escape_html(&mut html, description).unwrap();
+            html.push_str("</p>");
+        }
+        html.push_str("</a></li>");
+    }
+    html.push_str("</ul>");
+    if posts.is_empty() {
+        html.push_str("<p class='posts-empty'>");
+        escape_html(&mut html, &blog.empty).unwrap();
+        html.push_str("</p>");
+    }
+
+    let link = |n, title| context! { title, link => page_link(locale, n) };
+    html.push_str(&templates.render(
+        "page-nav.html",
+        context! {
+            prev => (n > 1).then(|| link(n - 1, &blog.newer)),
+            next => (n < pages).then(|| link(n + 1, &blog.older)),
+            labels => context! { prev => config.prev, next => config.next },
+        },
+    )?);
+
+    Ok(html)
+}
+
+fn atom(
+    config: &Config,
+    posts: &[Post],
+    contents: &[String],
+    updated: &str,
+    base_url: &str,
+) -> Result<String> {
+    let locale = &config.locale;
+    let home = format!("{base_url}/{locale}/blog");
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"");
+    xml.push_str(locale);
+    xml.push_str("\">\n  <title>");
+    escape_html(&mut xml, &config.blog.title)?;
+    xml.push_str("</title>\n  <subtitle>");
+    escape_html(&mut xml, &config.blog.description)?;
+    xml.push_str("</subtitle>\n  <id>");
+    xml.push_str(&home);
+    xml.push_str("</id>\n  <link href=\"");
+    xml.push_str(&home);
+    xml.push_str("\"/>\n  <link rel=\"self\" href=\"");
+    xml.push_str(&format!("{base_url}/docs/{locale}/blog/atom.xml"));
+    xml.push_str("\"/>\n  <updated>");
+    xml.push_str(updated);
+    xml.push_str("T00:00:00Z</updated>\n");
+
+    for (post, content) in posts.iter().zip(contents) {
+        let url = format!("{home}/{}", post.slug);
+        xml.push_str("  <entry>\n    <title>");
+        escape_html(&mut xml, post.meta.title.as_deref().unwrap_or(&post.slug))?;
+        xml.push_str("</title>\n    <id>");
+        xml.push_str(&url);
+        xml.push_str("</id>\n    <link href=\"");
+        xml.push_str(&url);
+        xml.push_str("\"/>\n    <updated>");
+        xml.push_str(&post.date);
+        xml.push_str("T00:00:00Z</updated>\n");
+        if let Some(description) = &post.meta.description {
+            xml.push_str("    <summary>");
+            escape_html(&mut xml, description)?;
+            xml.push_str("</summary>\n");
+        }
+        xml.push_str("    <content type=\"html\">");
+        escape_html(&mut xml, content)?;
+        xml.push_str("</content>\n  </entry>\n");
+    }
+
+    xml.push_str("</feed>\n");
+    Ok(xml)
+}
+
+fn rss(config: &Config, posts: &[Post], contents: &[String], base_url: &str) -> Result<String> {
+    let locale = &config.locale;
+    let home = format!("{base_url}/{locale}/blog");
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str(
+        "<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n<channel>\n  <title>",
+    );
+    escape_html(&mut xml, &config.blog.title)?;
+    xml.push_str("</title>\n  <link>");
+    xml.push_str(&home);
+    xml.push_str("</link>\n  <description>");
+    escape_html(&mut xml, &config.blog.description)?;
+    xml.push_str("</description>\n  <language>");
+    xml.push_str(locale);
+    xml.push_str("</language>\n  <atom:link href=\"");
+    xml.push_str(&format!("{base_url}/docs/{locale}/blog/rss.xml"));
+    xml.push_str("\" rel=\"self\" type=\"application/rss+xml\"/>\n");
+
+    for (post, content) in posts.iter().zip(contents) {
+        let url = format!("{home}/{}", post.slug);
+        xml.push_str("  <item>\n    <title>");
+        escape_html(&mut xml, post.meta.title.as_deref().unwrap_or(&post.slug))?;
+        xml.push_str("</title>\n    <link>");
+        xml.push_str(&url);
+        xml.push_str("</link>\n    <guid>");
+        xml.push_str(&url);
+        xml.push_str("</guid>\n    <pubDate>");
+        xml.push_str(&rfc822(&post.date).context("invalid post date")?);
+        xml.push_str("</pubDate>\n    <description>");
+        escape_html(&mut xml, content)?;
+        xml.push_str("</description>\n  </item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    Ok(xml)
+}
+
+/// Days since the unix epoch of a `YYYY-MM-DD` date, `None` unless zero-padded.
+fn days(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-');
+    let mut part = |len: usize| {
+        parts
+            .next()
+            .filter(|part| part.len() == len && part.bytes().all(|b| b.is_ascii_digit()))?
+            .parse::<i64>()
+            .ok()
+    };
+    let year = part(4)?;
+    let month = part(2)?;
+    let day = part(2)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Some(era * 146_097 + doe - 719_468)
+}
+
+/// Formats a `YYYY-MM-DD` date as `Mon, 02 Jan 2006 00:00:00 +0000`.
+fn rfc822(date: &str) -> Option<String> {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let weekday = WEEKDAYS[days(date)?.rem_euclid(7) as usize];
+    let (year, rest) = date.split_once('-')?;
+    let (month, day) = rest.split_once('-')?;
+    let month = MONTHS[month.parse::<usize>().ok()? - 1];
+    Some(format!("{weekday}, {day} {month} {year} 00:00:00 +0000"))
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    env::current_exe,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::{front_matter::FrontMatter, search};
+
+/// The cached result of a generated file.
+///
+/// Pages keep their metadata and search sections, so `meta.json` and `search.json`
+/// can still be written when the page itself is skipped.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Entry {
+    pub hash: u64,
+    #[serde(default)]
+    pub meta: FrontMatter,
+    #[serde(default)]
+    pub sections: Vec<search::Section>,
+}
+
+impl Entry {
+    pub fn new(hash: u64) -> Self {
+        Self {
+            hash,
+            ..Self::default()
+        }
+    }
+}
+
+/// Build cache of a locale, stored under `target/gen-cache`.
+#[derive(Debug, Default)]
+pub struct Cache {
+    path: PathBuf,
+    force: bool,
+    seen: BTreeSet<String>,
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Cache {
+    /// Loads the cache at `path`, a missing or unreadable cache is treated as empty.
+    pub fn load(path: PathBuf, force: bool) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            force,
+            seen: BTreeSet::new(),
+            entries,
+        }
+    }
+
+    /// Returns the cached entry of `key` if its hash matches and `output` still exists.
+    pub fn fresh(&mut self, key: &str, hash: u64, output: &Path) -> Option<Entry> {
+        self.seen.insert(key.to_string());
+        if self.force || !output.exists() {
+            return None;
+        }
+        self.entries.get(key).filter(|e| e.hash == hash).cloned()
+    }
+
+    pub fn insert(&mut self, key: String, entry: Entry) {
+        self.seen.insert(key.clone());
+        self.entries.insert(key, entry);
+    }
+
+    /// Drops the entries of removed files and writes the cache back to disk.
+    pub fn save(mut self) -> Result<()> {
+        let seen = self.seen;
+        self.entries.retain(|key, _| seen.contains(key));
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&self.entries)?)?;
+        Ok(())
+    }
+}
+
+pub fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes the inputs shared by every page of a locale: the locale config, the
+/// highlight queries and the gen binary itself.
+pub fn fingerprint(config: &str, queries: &Path) -> Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    config.hash(&mut hasher);
+
+    for entry in WalkDir::new(queries).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            entry.path().hash(&mut hasher);
+            fs::read(entry.path())?.hash(&mut hasher);
+        }
+    }
+
+    if let Ok(meta) = current_exe().and_then(fs::metadata) {
+        meta.len().hash(&mut hasher);
+        meta.modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .hash(&mut hasher);
+    }
+
+    Ok(hasher.finish())
+}
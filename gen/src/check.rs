@@ -0,0 +1,232 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use highlighting::Languages;
+use walkdir::WalkDir;
+
+use crate::{
+    find_prev_and_next, front_matter, image, load_config, locales, parse, read_toc, rustdoc::Api,
+    templates::Templates, versions,
+};
+
+/// A source file and the links it references.
+#[derive(Debug)]
+struct Page {
+    source: PathBuf,
+    url: String,
+    links: Vec<String>,
+    images: Vec<String>,
+    unresolved: Vec<String>,
+}
+
+/// Renders every locale and version in memory and reports dead page links,
+/// missing `#anchors` and images that are not copied into the output.
+pub fn check_links(root: &Path, languages: &Languages, templates: &Templates) -> Result<()> {
+    let mut anchors = BTreeMap::<String, BTreeSet<String>>::new();
+    let mut pages = Vec::new();
+
+    for locale in locales(root)? {
+        let (config, _) = load_config(root, &locale)?;
+        for version in versions(root, &locale)? {
+            // Without the API, undefined references are left unchecked.
+            let api = Api::load(root, &version)?;
+            if api.is_none() {
+                eprintln!(
+                    "warning: no gen/rustdoc/{version}.json, API references are not checked, \
+                     run `cargo make rustdoc`"
+                );
+            }
+            let dir = Path::new(&locale).join(&version);
+            let toc = read_toc(&root.join(&dir))?;
+
+            pages.push(Page {
+                source: dir.join("toc.json"),
+                url: format!("/{locale}/{version}/"),
+                links: toc
+                    .iter()
+                    .flat_map(|section| {
+                        section.items.iter().map(|(_, link)| {
+                            format!("/{locale}/{version}/{}/{link}", section.prefix)
+                        })
+                    })
+                    .collect(),
+                images: Vec::new(),
+                unresolved: Vec::new(),
+            });
+
+            for entry in WalkDir::new(root.join(&dir)).sort_by_file_name() {
+                let entry = entry?;
+                let path = entry.path();
+                if path.extension() != Some(OsStr::new("md")) {
+                    continue;
+                }
+
+                let (Some(dir), Some(stem)) = (
+                    path.parent()
+                        .and_then(Path::file_name)
+                        .and_then(OsStr::to_str),
+                    path.file_stem().and_then(OsStr::to_str),
+                ) else {
+                    continue;
+                };
+
+                let raw = fs::read_to_string(path)?;
+                let (meta, body) = front_matter::split(&raw)?;
+                if meta.draft {
+                    continue;
+                }
+
+                let navs = find_prev_and_next(&toc, &locale, &version, dir, stem);
+                let mut links = [&navs.0, &navs.1]
+                    .into_iter()
+                    .flatten()
+                    .map(|(_, link)| format!("/{link}"))
+                    .collect::<Vec<_>>();
+                let document = parse(
+                    &config,
+                    languages,
+                    templates,
+                    api.as_ref(),
+                    None,
+                    navs,
+                    meta,
+                    body,
+                )?;
+                let url = format!("/{locale}/{version}/{dir}/{stem}");
+
+                links.extend(document.links);
+                anchors.insert(url.clone(), document.anchors);
+                pages.push(Page {
+                    source: path.strip_prefix(root)?.to_path_buf(),
+                    url,
+                    links,
+                    images: document.images,
+                    unresolved: document.unresolved,
+                });
+            }
+        }
+    }
+
+    let mut problems = Vec::new();
+    for page in &pages {
+        for link in &page.links {
+            if let Some(problem) = check_link(&anchors, &page.url, link) {
+                problems.push(format!("{}: {problem}", page.source.display()));
+            }
+        }
+        for src in &page.images {
+            if let Some(problem) = check_image(root, &page.url, src) {
+                problems.push(format!("{}: {problem}", page.source.display()));
+            }
+        }
+        for name in &page.unresolved {
+            problems.push(format!("{}: unknown API `{name}`", page.source.display()));
+        }
+    }
+
+    for problem in &problems {
+        eprintln!("{problem}");
+    }
+    println!(
+        "checked {} pages, {} problems",
+        anchors.len(),
+        problems.len()
+    );
+
+    if !problems.is_empty() {
+        bail!("found {} broken links", problems.len());
+    }
+
+    Ok(())
+}
+
+fn check_link(
+    anchors: &BTreeMap<String, BTreeSet<String>>,
+    base: &str,
+    href: &str,
+) -> Option<String> {
+    if is_external(href) {
+        return None;
+    }
+
+    let (path, fragment) = href.split_once('#').unwrap_or((href, ""));
+    let path = path.split('?').next().unwrap_or_default();
+    let url = if path.is_empty() {
+        base.to_string()
+    } else {
+        resolve(base, path)
+    };
+
+    if url == "/" || url.starts_with("/docs/") {
+        return None;
+    }
+
+    let Some(ids) = anchors.get(url.trim_end_matches('/')) else {
+        return Some(format!("dead link `{href}`"));
+    };
+
+    if !fragment.is_empty() && !ids.contains(fragment) {
+        return Some(format!("missing anchor `{href}`"));
+    }
+
+    None
+}
+
+/// Images are served from `/docs/<locale>/<version>`, a mirror of the source tree.
+fn check_image(root: &Path, base: &str, src: &str) -> Option<String> {
+    if is_external(src) {
+        return None;
+    }
+
+    let url = resolve(base, src);
+    let Some(file) = url.strip_prefix("/docs/") else {
+        if root.join("app").join(url.trim_start_matches('/')).is_file() {
+            return None;
+        }
+        return Some(format!("image not found `{src}`"));
+    };
+
+    let file = root.join(file);
+    if !file.is_file() {
+        return Some(format!("image not found `{src}`"));
+    }
+
+    if !matches!(file.extension().and_then(OsStr::to_str), Some(e) if image::EXTENSIONS.contains(&e))
+    {
+        return Some(format!("image is not copied `{src}`"));
+    }
+
+    None
+}
+
+fn is_external(href: &str) -> bool {
+    href.starts_with("//") || href.contains(':')
+}
+
+/// Resolves `path` against the directory of the `base` url.
+fn resolve(base: &str, path: &str) -> String {
+    let joined = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        let dir = base.rsplit_once('/').map_or("", |(dir, _)| dir);
+        format!("{dir}/{path}")
+    };
+
+    let mut segments = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    format!("/{}", segments.join("/"))
+}
@@ -0,0 +1,370 @@
+use std::ops::RangeInclusive;
+
+use highlighting::Languages;
+use minijinja::{context, Error, Value};
+use pulldown_cmark::{escape::escape_html, CodeBlockKind, CowStr, Event, Tag};
+
+use crate::{registry, templates::Templates};
+
+/// The info string of a fenced code block,
+/// e.g. `rust title="src/main.rs" {3,7-9} showLineNumbers`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Info {
+    pub lang: String,
+    /// Rustdoc attributes following the language, e.g. `ignore` in `rust,ignore`.
+    pub attrs: Vec<String>,
+    pub title: Option<String>,
+    /// Label of the block in a group of adjacent tabbed blocks.
+    pub tab: Option<String>,
+    pub highlight: Vec<RangeInclusive<usize>>,
+    pub line_numbers: bool,
+    /// `diff` or `rust diff`, the `+` and `-` lines of a unified diff are marked as changed.
+    pub diff: bool,
+}
+
+impl Info {
+    pub fn parse(info: &str) -> Self {
+        let mut this = Self::default();
+
+        for (i, token) in tokens(info).into_iter().enumerate() {
+            if let Some(ranges) = token.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+                this.highlight.extend(ranges.split(',').filter_map(range));
+            } else if let Some(title) = token.strip_prefix("title=") {
+                this.title = Some(unquote(title));
+            } else if let Some(tab) = token.strip_prefix("tab=") {
+                this.tab = Some(unquote(tab));
+            } else if token == "showLineNumbers" {
+                this.line_numbers = true;
+            } else if token == "diff" {
+                this.diff = true;
+                if i == 0 {
+                    this.lang = token;
+                }
+            } else if i == 0 {
+                let mut attrs = token.split(',');
+                this.lang = attrs.next().unwrap_or_default().to_string();
+                this.attrs = attrs.map(ToString::to_string).collect();
+            }
+        }
+
+        this
+    }
+
+    fn highlighted(&self, line: usize) -> bool {
+        self.highlight.iter().any(|r| r.contains(&line))
+    }
+
+    /// Removes the `+`/`-` markers of a diff block, so the code is highlighted by
+    /// its own grammar, and returns the mark of every line.
+    pub fn strip_diff(&self, code: &str) -> (String, Vec<Option<&'static str>>) {
+        if !self.diff {
+            return (code.to_string(), Vec::new());
+        }
+
+        // Only unified diffs are stripped, they prefix unchanged lines with a space,
+        // other blocks keep their lines, such as `-` or an indented line, as written.
+        if !code
+            .lines()
+            .all(|line| line.is_empty() || line.starts_with(['+', '-', ' ']))
+        {
+            return (code.to_string(), Vec::new());
+        }
+
+        let mut stripped = String::with_capacity(code.len());
+        let mut marks = Vec::new();
+        for line in code.split_inclusive('\n') {
+            let (mark, line) = if let Some(line) = line.strip_prefix('+') {
+                (Some("add"), line)
+            } else if let Some(line) = line.strip_prefix('-') {
+                (Some("remove"), line)
+            } else {
+                (None, line.strip_prefix(' ').unwrap_or(line))
+            };
+            marks.push(mark);
+            stripped.push_str(line);
+        }
+
+        (stripped, marks)
+    }
+
+    /// Renders the highlighted `html` with its line marks, when asked for, in the
+    /// `code.html` template.
+    pub fn render(
+        &self,
+        templates: &Templates,
+        html: &str,
+        marks: &[Option<&str>],
+    ) -> Result<String, Error> {
+        let mut code = String::new();
+        if self.highlight.is_empty() && !self.line_numbers && marks.is_empty() {
+            code.push_str(html);
+        } else {
+            self.lines(&mut code, html, marks);
+        }
+
+        templates.render(
+            "code.html",
+            context! {
+                lang => self.lang,
+                title => self.title,
+                tab => self.tab,
+                line_numbers => self.line_numbers,
+                code => Value::from_safe_string(code),
+            },
+        )
+    }
+
+    /// Splits the content of `<code>` into lines, closing the open tags at the end of
+    /// a line and reopening them on the next one, so every line can be wrapped.
+    fn lines(&self, output: &mut String, html: &str, marks: &[Option<&str>]) {
+        let start = html
+            .find("<code")
+            .and_then(|i| html[i..].find('>').map(|j| i + j + 1));
+        let end = html.rfind("</code>");
+        let Some((start, end)) = start.zip(end).filter(|(start, end)| start <= end) else {
+            output.push_str(html);
+            return;
+        };
+
+        output.push_str(&html[..start]);
+
+        // The trailing newline of the block, possibly followed by closing tags.
+        let mut body = html[start..end].to_string();
+        if let Some(i) = body.rfind('\n') {
+            if body[i + 1..].split('<').all(|s| {
+                s.split_once('>')
+                    .map_or(s.is_empty(), |(_, text)| text.is_empty())
+            }) {
+                body.remove(i);
+            }
+        }
+        // Open tags and their names.
+        let mut open: Vec<(&str, &str)> = Vec::new();
+        for (i, line) in body.split('\n').enumerate() {
+            let n = i + 1;
+            if i > 0 {
+                output.push('\n');
+            }
+
+            let mut class = Vec::new();
+            if self.highlighted(n) {
+                class.push("highlighted");
+            }
+            if let Some(mark) = marks.get(i).copied().flatten() {
+                class.push("diff");
+                class.push(mark);
+            }
+            if !class.is_empty() {
+                output.push_str("<span class='");
+                output.push_str(&class.join(" "));
+                output.push_str("'>");
+            }
+            if self.line_numbers {
+                output.push_str("<span class='line-no'>");
+                output.push_str(&n.to_string());
+                output.push_str("</span>");
+            }
+            for (tag, _) in &open {
+                output.push_str(tag);
+            }
+
+            let mut rest = line;
+            while let Some(lt) = rest.find('<') {
+                let Some(gt) = rest[lt..].find('>').map(|gt| lt + gt + 1) else {
+                    break;
+                };
+                let tag = &rest[lt..gt];
+                if tag.starts_with("</") {
+                    open.pop();
+                } else if !tag.ends_with("/>") {
+                    let name = tag[1..tag.len() - 1]
+                        .split(|c: char| c.is_whitespace())
+                        .next()
+                        .unwrap_or_default();
+                    open.push((tag, name));
+                }
+                output.push_str(&rest[..gt]);
+                rest = &rest[gt..];
+            }
+            output.push_str(rest);
+
+            for (_, name) in open.iter().rev() {
+                output.push_str("</");
+                output.push_str(name);
+                output.push('>');
+            }
+            if !class.is_empty() {
+                output.push_str("</span>");
+            }
+        }
+
+        output.push_str(&html[end..]);
+    }
+}
+
+/// Wraps adjacent fenced blocks with a `tab="label"` into a tab group.
+///
+/// The first tab is active until the app restores the label chosen last.
+pub fn group<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut events = events.into_iter().peekable();
+
+    while let Some(event) = events.next() {
+        if tab(&event).is_none() {
+            output.push(event);
+            continue;
+        }
+
+        let mut labels = Vec::new();
+        let mut blocks = Vec::new();
+        let mut next = Some(event);
+        while let Some(label) = next.as_ref().and_then(tab) {
+            labels.push(label);
+            blocks.extend(next.take());
+            for event in events.by_ref() {
+                let end = matches!(event, Event::End(Tag::CodeBlock(_)));
+                blocks.push(event);
+                if end {
+                    break;
+                }
+            }
+            next = events.next_if(|event| tab(event).is_some());
+        }
+
+        let mut open = String::new();
+        open.push_str("<div class='code-group'><div class='tabs'>");
+        for (i, label) in labels.iter().enumerate() {
+            open.push_str("<button class='tab");
+            if i == 0 {
+                open.push_str(" active");
+            }
+            open.push_str("' data-tab='");
+            escape_html(&mut open, label).unwrap();
+            open.push_str("'>");
+            escape_html(&mut open, label).unwrap();
+            open.push_str("</button>");
+        }
+        open.push_str("</div>");
+
+        output.push(Event::Html(CowStr::from(open)));
+        output.extend(blocks);
+        output.push(Event::Html(CowStr::Borrowed("</div>")));
+    }
+
+    output
+}
+
+/// Highlights `code` with the grammar of `lang`, falling back to plain text.
+///
+/// In a `shell-session` the commands following a `$ ` prompt, and their `\`
+/// continuation lines, are highlighted as `bash` while the output stays plain.
+pub fn highlight(languages: &Languages, lang: &str, code: &str) -> String {
+    if lang != registry::SESSION {
+        return languages
+            .render(lang, code.as_bytes())
+            .unwrap_or_else(|| plain(lang, code));
+    }
+
+    let mut html = String::from("<pre class='language-shell-session'><code>");
+    let mut continued = false;
+    for line in code.split_inclusive('\n') {
+        let (text, newline) = match line.strip_suffix('\n') {
+            Some(text) => (text, "\n"),
+            None => (line, ""),
+        };
+        let command = if continued {
+            Some(text)
+        } else if let Some(command) = text.strip_prefix("$ ") {
+            html.push_str("<span class='prompt'>$ </span>");
+            Some(command)
+        } else {
+            None
+        };
+        match command {
+            Some(command) => {
+                continued = command.ends_with('\\');
+                match languages.render("bash", command.as_bytes()) {
+                    Some(bash) => html.push_str(inner(&bash).trim_end_matches('\n')),
+                    None => escape_html(&mut html, command).unwrap(),
+                }
+            }
+            None => {
+                html.push_str("<span class='output'>");
+                escape_html(&mut html, text).unwrap();
+                html.push_str("</span>");
+            }
+        }
+        html.push_str(newline);
+    }
+    html.push_str("</code></pre>");
+    html
+}
+
+/// The content of the `<code>` element of a highlighted block.
+fn inner(html: &str) -> &str {
+    let start = html
+        .find("<code")
+        .and_then(|i| html[i..].find('>').map(|j| i + j + 1));
+    match (start, html.rfind("</code>")) {
+        (Some(start), Some(end)) if start <= end => &html[start..end],
+        _ => html,
+    }
+}
+
+/// Renders a block of a language without a grammar.
+pub fn plain(lang: &str, code: &str) -> String {
+    let mut html = String::new();
+    html.push_str("<pre class='language-");
+    escape_html(&mut html, lang).unwrap();
+    html.push_str("'><code>");
+    escape_html(&mut html, code).unwrap();
+    html.push_str("</code></pre>");
+    html
+}
+
+fn tab(event: &Event) -> Option<String> {
+    match event {
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => Info::parse(info).tab,
+        _ => None,
+    }
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+/// Splits on whitespace, keeping quoted values together.
+fn tokens(info: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quote = None;
+
+    for c in info.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c.is_whitespace() => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        token.push(c);
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+/// Parses `3` or `7-9`.
+fn range(s: &str) -> Option<RangeInclusive<usize>> {
+    let s = s.trim();
+    match s.split_once('-') {
+        Some((a, b)) => Some(a.trim().parse().ok()?..=b.trim().parse().ok()?),
+        None => s.parse().ok().map(|n| n..=n),
+    }
+}
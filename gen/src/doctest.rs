@@ -0,0 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result};
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser as MarkParser, Tag};
+use serde_json::Value;
+use walkdir::WalkDir;
+
+use crate::{code::Info, front_matter, registry, versions};
+
+/// Features of viz used across the guides, on top of the default ones.
+const FEATURES: &str = r#"["compression", "cookie-private", "cookie-signed", "cors", "csrf", "embed", "handlers", "http2", "macros", "otel-tracing", "rustls", "serve", "sse", "websocket"]"#;
+
+/// Crates the snippets may use besides viz.
+const DEPENDENCIES: &str = r#"tokio = { version = "1", features = ["full"] }
+serde = { version = "1", features = ["derive"] }
+serde_json = "1"
+anyhow = "1"
+"#;
+
+/// A rust code block, compiled as its own binary of the scratch crate.
+#[derive(Debug)]
+struct Snippet {
+    source: PathBuf,
+    /// Markdown line of the first line of code.
+    line: usize,
+    /// Lines added in front of the code.
+    offset: usize,
+    compile_fail: bool,
+}
+
+/// Extracts the rust code blocks of every version of `locale` into a scratch crate
+/// under `target/gen-doctest` and runs `cargo check` on it.
+///
+/// Like rustdoc, `ignore` blocks are skipped and `compile_fail` blocks must not
+/// compile. `no_run` needs no handling as nothing is run. Diff blocks are skipped.
+///
+/// `viz` is a checkout of viz, or a directory with one checkout per version
+/// (`<viz>/0.4.x`). Versions without a checkout use the release from crates.io.
+pub fn compile_check(root: &Path, locale: &str, viz: Option<&Path>) -> Result<()> {
+    let mut failed = 0;
+
+    for version in versions(root, locale)? {
+        let dir = root.join("target/gen-doctest").join(locale).join(&version);
+        let snippets = extract(&root.join(locale).join(&version), &dir.join("src/bin"))?;
+        if snippets.is_empty() {
+            continue;
+        }
+
+        fs::write(dir.join("Cargo.toml"), manifest(&version, viz)?)?;
+
+        println!("checking {} snippets of {locale}/{version}", snippets.len());
+        let output = Command::new("cargo")
+            .args(["check", "--bins", "--keep-going", "--message-format=json"])
+            .current_dir(&dir)
+            .output()
+            .context("failed to run cargo")?;
+
+        let errors = errors(&output.stdout);
+        for (name, snippet) in &snippets {
+            let messages = errors.get(name.as_str());
+            match (snippet.compile_fail, messages) {
+                (false, Some(messages)) => {
+                    for (line, message) in messages {
+                        let line = (snippet.line + line).saturating_sub(snippet.offset + 1);
+                        eprintln!("{}:{line}: {message}", snippet.source.display());
+                        failed += 1;
+                    }
+                }
+                (true, None) => {
+                    eprintln!(
+                        "{}:{}: snippet marked `compile_fail` compiles",
+                        snippet.source.display(),
+                        snippet.line
+                    );
+                    failed += 1;
+                }
+                _ => {}
+            }
+        }
+
+        // Errors outside the snippets, e.g. an unresolvable dependency.
+        let outside = errors.keys().any(|name| !snippets.contains_key(name));
+        if outside || (errors.is_empty() && !output.status.success()) {
+            eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} snippets failed to compile");
+    }
+
+    Ok(())
+}
+
+/// Writes every checked snippet under `root` as a binary in `bin`.
+fn extract(root: &Path, bin: &Path) -> Result<BTreeMap<String, Snippet>> {
+    if bin.exists() {
+        fs::remove_dir_all(bin)?;
+    }
+    fs::create_dir_all(bin)?;
+
+    let mut snippets = BTreeMap::new();
+    for entry in WalkDir::new(root).sort_by_file_name() {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension() != Some(OsStr::new("md")) {
+            continue;
+        }
+
+        let raw = fs::read_to_string(path)?;
+        let (meta, body) = front_matter::split(&raw)?;
+        if meta.draft {
+            continue;
+        }
+        // Lines of the front matter.
+        let skipped = raw[..raw.len() - body.len()].lines().count();
+
+        let mut block = None;
+        let mut code = String::new();
+        for (event, range) in MarkParser::new_ext(body, Options::all()).into_offset_iter() {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                    let info = Info::parse(&info);
+                    if registry::resolve(&info.lang) == "rust"
+                        && !info.diff
+                        && !info.attrs.iter().any(|a| a == "ignore")
+                    {
+                        let line = skipped + body[..range.start].lines().count() + 2;
+                        block = Some((info, line));
+                    }
+                    code.clear();
+                }
+                Event::Text(text) if block.is_some() => code.push_str(&text),
+                Event::End(Tag::CodeBlock(_)) => {
+                    let Some((info, line)) = block.take() else {
+                        continue;
+                    };
+
+                    let (program, offset) = if code.contains("fn main") {
+                        (format!("#![allow(unused)]\n{code}"), 1)
+                    } else {
+                        (format!("#![allow(unused)]\nfn main() {{\n{code}\n}}\n"), 2)
+                    };
+
+                    let name = format!(
+                        "{}_{}",
+                        path.strip_prefix(root)?.with_extension("").display(),
+                        line
+                    )
+                    .replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+                    fs::write(bin.join(&name).with_extension("rs"), program)?;
+                    snippets.insert(
+                        name,
+                        Snippet {
+                            source: path.to_path_buf(),
+                            line,
+                            offset,
+                            compile_fail: info.attrs.iter().any(|a| a == "compile_fail"),
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(snippets)
+}
+
+fn manifest(version: &str, viz: Option<&Path>) -> Result<String> {
+    let checkout = viz
+        .map(|viz| {
+            if viz.join(version).is_dir() {
+                viz.join(version)
+            } else {
+                viz.to_path_buf()
+            }
+        })
+        .map(|dir| {
+            if dir.join("viz/Cargo.toml").is_file() {
+                dir.join("viz")
+            } else {
+                dir
+            }
+        });
+
+    let viz = match checkout {
+        Some(dir) => format!(
+            "viz = {{ path = {:?}, features = {FEATURES} }}",
+            fs::canonicalize(&dir)
+                .with_context(|| format!("viz checkout not found at {}", dir.display()))?
+        ),
+        None => format!(
+            "viz = {{ version = \"{}\", features = {FEATURES} }}",
+            version.trim_end_matches(".x")
+        ),
+    };
+
+    Ok(format!(
+        "[package]\nname = \"doctest\"\nversion = \"0.0.0\"\nedition = \"2021\"\npublish = false\n\n\
+         [workspace]\n\n[dependencies]\n{viz}\n{DEPENDENCIES}"
+    ))
+}
+
+/// Groups the errors of `cargo check` by binary, with the line of the snippet file.
+fn errors(stdout: &[u8]) -> BTreeMap<String, Vec<(usize, String)>> {
+    let mut errors = BTreeMap::<String, Vec<(usize, String)>>::new();
+
+    for line in String::from_utf8_lossy(stdout).lines() {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if message["reason"] != "compiler-message" || message["message"]["level"] != "error" {
+            continue;
+        }
+
+        let Some(name) = message["target"]["name"].as_str() else {
+            continue;
+        };
+        let line = message["message"]["spans"]
+            .as_array()
+            .and_then(|spans| spans.iter().find(|span| span["is_primary"] == true))
+            .and_then(|span| span["line_start"].as_u64())
+            .unwrap_or_default() as usize;
+        let text = message["message"]["message"].as_str().unwrap_or_default();
+        let text = match message["message"]["code"]["code"].as_str() {
+            Some(code) => format!("error[{code}]: {text}"),
+            None => format!("error: {text}"),
+        };
+
+        errors
+            .entry(name.to_string())
+            .or_default()
+            .push((line, text));
+    }
+
+    errors
+}
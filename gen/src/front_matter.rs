@@ -0,0 +1,66 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Per-page metadata declared at the top of a markdown file.
+///
+/// YAML front matter is fenced by `---`, TOML front matter by `+++`.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FrontMatter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since_version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    /// Publication date of a blog post, `YYYY-MM-DD`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Git blob hash of the `en` page a translation was made from.
+    #[serde(skip_serializing)]
+    pub source_hash: Option<String>,
+}
+
+/// Splits the front matter off `raw`, returning it with the remaining markdown body.
+///
+/// Files without front matter get a default `FrontMatter` and are returned untouched.
+pub fn split(raw: &str) -> Result<(FrontMatter, &str)> {
+    let raw = raw.trim_start_matches('\u{feff}');
+
+    for fence in ["---", "+++"] {
+        let Some(rest) = raw.strip_prefix(fence).and_then(strip_newline) else {
+            continue;
+        };
+
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == fence {
+                let block = &rest[..offset];
+                let body = &rest[offset + line.len()..];
+                let matter = if block.trim().is_empty() {
+                    FrontMatter::default()
+                } else if fence == "---" {
+                    serde_yaml::from_str(block)?
+                } else {
+                    toml::from_str(block)?
+                };
+                return Ok((matter, body));
+            }
+            offset += line.len();
+        }
+    }
+
+    Ok((FrontMatter::default(), raw))
+}
+
+fn strip_newline(s: &str) -> Option<&str> {
+    s.strip_prefix("\r\n").or_else(|| s.strip_prefix('\n'))
+}
@@ -0,0 +1,202 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use image::{codecs::avif::AvifEncoder, imageops::FilterType, ExtendedColorType, ImageEncoder};
+use pulldown_cmark::{escape::escape_html, Event, Tag};
+use resvg::usvg::{Options, Tree};
+
+/// Image extensions copied into the output directory.
+pub const EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "svg", "webp"];
+
+/// Widths of the resized variants, those narrower than the image are generated.
+const WIDTHS: [u32; 2] = [480, 960];
+
+/// Displayed width of an image, at most the width of the article.
+const SIZES: &str = "(min-width: 960px) 960px, 100vw";
+
+/// Quality of the lossy variants, out of 100.
+const WEBP_QUALITY: f32 = 80.0;
+const AVIF_QUALITY: u8 = 70;
+/// Speed of the AVIF encoder, from 1, the smallest files, to 10.
+const AVIF_SPEED: u8 = 6;
+
+/// Formats of the variants, the preferred first.
+const FORMATS: [(&str, &str); 2] = [("avif", "image/avif"), ("webp", "image/webp")];
+
+/// Size of an image and the widths of its resized AVIF and WebP variants.
+///
+/// PNG, JPEG and WebP images have lossy variants at the smaller widths and at their
+/// own, GIFs are kept as is since they may be animated. Browsers without AVIF or
+/// WebP support get the original.
+#[derive(Debug)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub variants: Vec<u32>,
+}
+
+impl Image {
+    /// Reads the size of the image at `path` without decoding it.
+    pub fn load(path: &Path) -> Result<Self> {
+        let (width, height) = if path.extension().is_some_and(|e| e == "svg") {
+            let size = Tree::from_data(&fs::read(path)?, &Options::default())?.size();
+            (size.width().round() as u32, size.height().round() as u32)
+        } else {
+            let size = imagesize::size(path)
+                .map_err(|e| anyhow!("{e}"))
+                .with_context(|| format!("invalid image {}", path.display()))?;
+            (size.width as u32, size.height as u32)
+        };
+
+        let mut variants = Vec::new();
+        if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("png" | "jpg" | "jpeg" | "webp")
+        ) {
+            variants.extend(WIDTHS.into_iter().filter(|w| *w < width));
+            variants.push(width);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            variants,
+        })
+    }
+
+    /// Writes the image `raw` to `fp` and its variants next to it as
+    /// `<stem>-<width>w.avif` and `<stem>-<width>w.webp`.
+    pub fn write(&self, raw: &[u8], fp: &Path) -> Result<()> {
+        fs::write(fp, raw)?;
+        if self.variants.is_empty() {
+            return Ok(());
+        }
+
+        let source = image::load_from_memory(raw)
+            .with_context(|| format!("invalid image {}", fp.display()))?;
+        let name = fp.to_string_lossy();
+        for &width in &self.variants {
+            let height =
+                ((u64::from(self.height) * u64::from(width) / u64::from(self.width)) as u32).max(1);
+            let pixels = if width == source.width() {
+                source.to_rgba8()
+            } else {
+                source
+                    .resize_exact(width, height, FilterType::Lanczos3)
+                    .to_rgba8()
+            };
+            let (width, height) = pixels.dimensions();
+
+            let mut avif = Vec::new();
+            AvifEncoder::new_with_speed_quality(&mut avif, AVIF_SPEED, AVIF_QUALITY).write_image(
+                &pixels,
+                width,
+                height,
+                ExtendedColorType::Rgba8,
+            )?;
+            fs::write(fp.with_file_name(variant(&name, width, "avif")), avif)?;
+
+            let webp = webp::Encoder::from_rgba(&pixels, width, height)
+                .encode_simple(false, WEBP_QUALITY)
+                .map_err(|e| anyhow!("cannot encode {}: {e:?}", fp.display()))?;
+            fs::write(fp.with_file_name(variant(&name, width, "webp")), &*webp)?;
+        }
+
+        Ok(())
+    }
+
+    /// Paths of the variants [`Image::write`] writes next to `fp`.
+    pub fn outputs<'a>(&'a self, fp: &'a Path) -> impl Iterator<Item = PathBuf> + 'a {
+        let name = fp.to_string_lossy();
+        self.variants.iter().flat_map(move |&width| {
+            FORMATS.map(|(ext, _)| fp.with_file_name(variant(&name, width, ext)))
+        })
+    }
+}
+
+/// File name of the `ext` variant of `src` at `width`.
+fn variant(src: &str, width: u32, ext: &str) -> String {
+    let stem = src.rsplit_once('.').map_or(src, |(stem, _)| stem);
+    let stem = stem.rsplit('/').next().unwrap_or(stem);
+    format!("{stem}-{width}w.{ext}")
+}
+
+/// Renders an `<img>` sized to avoid layout shifts, in a `<picture>` offering its
+/// AVIF and WebP variants, in a `<figure>` captioned by its `title` if any. `image`
+/// is `None` for remote or missing images.
+pub fn html(image: Option<&Image>, src: &str, alt: &str, title: &str) -> String {
+    let mut html = String::new();
+    if !title.is_empty() {
+        html.push_str("<figure>");
+    }
+
+    let variants = image.map_or(&[][..], |image| &image.variants);
+    if !variants.is_empty() {
+        let dir = src.rsplit_once('/').map_or("", |(dir, _)| dir);
+        html.push_str("<picture>");
+        for (ext, mime) in FORMATS {
+            html.push_str("<source type='");
+            html.push_str(mime);
+            html.push_str("' srcset='");
+            for (i, width) in variants.iter().enumerate() {
+                if i > 0 {
+                    html.push_str(", ");
+                }
+                escape_html(&mut html, dir).unwrap();
+                html.push('/');
+                escape_html(&mut html, &variant(src, *width, ext)).unwrap();
+                html.push_str(&format!(" {width}w"));
+            }
+            html.push_str(&format!("' sizes='{SIZES}' />"));
+        }
+    }
+
+    html.push_str("<img alt='");
+    escape_html(&mut html, alt).unwrap();
+    html.push_str("' src='");
+    escape_html(&mut html, src).unwrap();
+    html.push('\'');
+    if let Some(image) = image {
+        html.push_str(&format!(
+            " width='{}' height='{}'",
+            image.width, image.height
+        ));
+    }
+    html.push_str(" loading='lazy' decoding='async' />");
+    if !variants.is_empty() {
+        html.push_str("</picture>");
+    }
+
+    if !title.is_empty() {
+        html.push_str("<figcaption>");
+        escape_html(&mut html, title).unwrap();
+        html.push_str("</figcaption></figure>");
+    }
+    html
+}
+
+/// Unwraps the paragraphs holding nothing but an image with a title, which is
+/// rendered as a `<figure>` that cannot be inside a `<p>`.
+pub fn figures(mut events: Vec<Event>) -> Vec<Event> {
+    let mut i = 0;
+    while i < events.len() {
+        if let (Event::Start(Tag::Paragraph), Some(Event::Start(Tag::Image(_, _, title)))) =
+            (&events[i], events.get(i + 1))
+        {
+            let end = events[i + 1..]
+                .iter()
+                .position(|event| matches!(event, Event::End(Tag::Image(..))))
+                .map(|n| i + 1 + n)
+                .filter(|end| matches!(events.get(end + 1), Some(Event::End(Tag::Paragraph))));
+            if let Some(end) = end.filter(|_| !title.is_empty()) {
+                events.remove(end + 1);
+                events.remove(i);
+            }
+        }
+        i += 1;
+    }
+    events
+}
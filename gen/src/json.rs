@@ -0,0 +1,92 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{Config, Document, Outline};
+
+/// A page written as `<page>.json` with `--json`, from which the app renders the
+/// outline, the page navigation and the metadata as views around the `html`.
+///
+/// Their labels are those of the locale config, like in the html pages, the app has
+/// its own only for the metadata that gen does not render.
+#[derive(Debug, Serialize)]
+pub struct Page<'a> {
+    pub title: Option<&'a str>,
+    pub description: Option<&'a str>,
+    /// The rendered markdown, without the page navigation and the outline.
+    pub html: &'a str,
+    pub toc: &'a [Outline],
+    pub prev: Option<Link<'a>>,
+    pub next: Option<Link<'a>>,
+    /// Date of the last commit of the source, `YYYY-MM-DD`, none for listings.
+    pub last_updated: Option<&'a str>,
+    /// Path of the markdown source in the repository, none for listings.
+    pub source_path: Option<String>,
+    pub labels: Labels<'a>,
+}
+
+/// The labels of `page-nav.html` and `outline.html`.
+#[derive(Debug, Serialize)]
+pub struct Labels<'a> {
+    pub prev: &'a str,
+    pub next: &'a str,
+    /// The title of the "On this page" outline.
+    pub outline: &'a str,
+}
+
+/// A link of the page navigation, without leading `/` like in `page-nav.html`.
+#[derive(Debug, Serialize)]
+pub struct Link<'a> {
+    pub title: &'a str,
+    pub link: &'a str,
+}
+
+impl<'a> Page<'a> {
+    /// The page of the markdown file at `source`, rendered with `config` as
+    /// `document` whose minified body is `html`.
+    pub fn new(
+        config: &'a Config,
+        document: &'a Document,
+        html: &'a str,
+        source: &Path,
+        last_updated: &'a str,
+    ) -> Self {
+        Self {
+            title: document.meta.title.as_deref(),
+            description: document.meta.description.as_deref(),
+            html,
+            toc: &document.outline,
+            prev: document.prev.as_ref().map(Link::from),
+            next: document.next.as_ref().map(Link::from),
+            last_updated: Some(last_updated),
+            source_path: Some(source.to_string_lossy().replace('\\', "/")),
+            labels: Labels::from(config),
+        }
+    }
+}
+
+/// Deletes the document `fp` of an earlier build with `--json`, which the app would
+/// render instead of the page.
+pub fn remove(fp: &Path) -> Result<()> {
+    if fp.is_file() {
+        fs::remove_file(fp)?;
+    }
+    Ok(())
+}
+
+impl<'a> From<&'a Config> for Labels<'a> {
+    fn from(config: &'a Config) -> Self {
+        Self {
+            prev: &config.prev,
+            next: &config.next,
+            outline: &config.title,
+        }
+    }
+}
+
+impl<'a> From<&'a (String, String)> for Link<'a> {
+    fn from((title, link): &'a (String, String)) -> Self {
+        Self { title, link }
+    }
+}
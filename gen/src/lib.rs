@@ -0,0 +1,784 @@
+//! Renders the markdown docs and blog of viz.rs into the html, json and images
+//! served to the app.
+//!
+//! Paths are relative to the root of the repository, [`Builder::root`]: the content
+//! is in `<locale>/<version>` and `<locale>/blog`, the configs in `gen/locales`.
+//!
+//! ```no_run
+//! use gen::Site;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let site = Site::builder().json(true).build()?;
+//!
+//! let config = site.config("en")?;
+//! let document = site.render(&config, "0.4.x", "# Hello\n\nWorld")?;
+//! assert_eq!(document.meta.title.as_deref(), Some("Hello"));
+//!
+//! site.build(&[("en".to_string(), "app/docs/en".into())])?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![allow(clippy::too_many_lines)]
+
+use std::{
+    collections::BTreeSet,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use highlighting::Languages;
+use minijinja::{context, Value};
+use pulldown_cmark::{
+    html::push_html, BrokenLink, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, Options,
+    Parser as MarkParser, Tag,
+};
+use serde::{Deserialize, Serialize};
+
+mod admonition;
+mod blog;
+mod cache;
+mod check;
+mod code;
+mod doctest;
+mod front_matter;
+mod image;
+mod json;
+mod manifest;
+mod og;
+mod prerender;
+mod registry;
+mod report;
+mod rustdoc;
+mod search;
+mod site;
+mod sitemap;
+mod slug;
+mod templates;
+
+pub use admonition::Admonitions;
+pub use blog::Blog;
+pub use front_matter::FrontMatter;
+pub use site::{Builder, Outputs, Site, Summary};
+pub use slug::SlugConfig;
+
+use image::Image;
+use manifest::Manifest;
+use rustdoc::Api;
+use slug::Slugger;
+use templates::Templates;
+
+/// A section of the sidebar, from the `toc.json` of a version.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Section {
+    pub text: String,
+    pub prefix: String,
+    /// Titles and file stems of the pages.
+    pub items: Vec<(String, String)>,
+}
+
+/// Labels and rules of a locale, `gen/locales/<locale>.toml`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Config {
+    #[serde(skip)]
+    pub locale: String,
+    /// Content directory of the locale, `<locale>` in the root of the repository.
+    #[serde(skip)]
+    pub dir: PathBuf,
+    pub title: String,
+    pub prev: String,
+    pub next: String,
+    #[serde(default)]
+    pub slug: SlugConfig,
+    /// Deepest heading level listed in the "On this page" outline, `3` or `4`.
+    #[serde(default = "default_outline")]
+    pub outline: u8,
+    #[serde(default)]
+    pub admonitions: Admonitions,
+    #[serde(default)]
+    pub blog: Blog,
+}
+
+fn default_outline() -> u8 {
+    3
+}
+
+type Navs = (Option<(String, String)>, Option<(String, String)>, String);
+
+/// A rendered markdown page.
+#[derive(Debug)]
+pub struct Document {
+    /// The article with its page navigation, followed by the outline.
+    pub html: String,
+    /// The rendered markdown, the `html` without the page navigation and the outline.
+    pub body: String,
+    pub outline: Vec<Outline>,
+    /// Titles and links of the previous and the next pages.
+    pub prev: Option<(String, String)>,
+    pub next: Option<(String, String)>,
+    /// The front matter, with the title and the description found in the page.
+    pub meta: FrontMatter,
+    pub(crate) sections: Vec<search::Section>,
+    pub anchors: BTreeSet<String>,
+    pub links: Vec<String>,
+    pub images: Vec<String>,
+    /// API names without a docs.rs page in the viz version of the page.
+    pub unresolved: Vec<String>,
+}
+
+/// Reads `gen/locales/<locale>.toml` of `root`, returning the config and its raw
+/// source.
+fn load_config(root: &Path, locale: &str) -> Result<(Config, String)> {
+    let path = root.join("gen/locales").join(format!("{locale}.toml"));
+    let raw = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let mut config = toml::from_str::<Config>(&raw)?;
+    config.locale = locale.to_string();
+    config.dir = root.join(locale);
+    Ok((config, raw))
+}
+
+/// Locales of `root` that have both a `gen/locales/<locale>.toml` config and a
+/// content directory.
+fn locales(root: &Path) -> Result<Vec<String>> {
+    let mut locales = Vec::new();
+    let dir = root.join("gen/locales");
+    for entry in fs::read_dir(&dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "toml") {
+            if let Some(locale) = path.file_stem().and_then(OsStr::to_str) {
+                if root.join(locale).is_dir() {
+                    locales.push(locale.to_string());
+                }
+            }
+        }
+    }
+    locales.sort();
+    Ok(locales)
+}
+
+/// Version directories of a locale of `root`, those containing a `toc.json`.
+fn versions(root: &Path, locale: &str) -> Result<Vec<String>> {
+    let mut versions = Vec::new();
+    let dir = root.join(locale);
+    for entry in fs::read_dir(&dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        if path.join("toc.json").is_file() {
+            if let Some(version) = path.file_name().and_then(OsStr::to_str) {
+                versions.push(version.to_string());
+            }
+        }
+    }
+    versions.sort();
+    Ok(versions)
+}
+
+/// Reads the `toc.json` of the version directory `root`, leaving out the pages
+/// marked as drafts so that they are neither listed nor linked as prev / next.
+fn read_toc(root: &Path) -> Result<Vec<Section>> {
+    let mut toc =
+        serde_json::from_str::<Vec<Section>>(&fs::read_to_string(root.join("toc.json"))?)?;
+    for section in &mut toc {
+        let mut items = Vec::with_capacity(section.items.len());
+        for (text, link) in section.items.drain(..) {
+            let path = root.join(&section.prefix).join(&link).with_extension("md");
+            if path.is_file() && front_matter::split(&fs::read_to_string(&path)?)?.0.draft {
+                continue;
+            }
+            items.push((text, link));
+        }
+        section.items = items;
+    }
+    toc.retain(|section| !section.items.is_empty());
+    Ok(toc)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn parse(
+    config: &Config,
+    languages: &Languages,
+    templates: &Templates,
+    api: Option<&Api>,
+    assets: Option<&Manifest>,
+    navs: Navs,
+    mut meta: FrontMatter,
+    raw: &str,
+) -> Result<Document> {
+    let options = Options::all();
+    // First error of the templates, rendered while iterating the events.
+    let mut error = None;
+    let mut toc = Vec::new();
+    let mut heading: Option<Vec<Event>> = None;
+    let mut slugger = Slugger::new(&config.slug);
+    let mut code = None;
+    let mut img = None;
+    // Text of the first paragraph, the description of pages without one.
+    let mut paragraph: Option<String> = None;
+    let mut search = search::Collector::default();
+    let mut anchors = BTreeSet::new();
+    let mut links = Vec::new();
+    let mut images = Vec::new();
+    let mut unresolved = Vec::new();
+    // Undefined references such as [`Router`] link to docs.rs, the definitions of a
+    // page take precedence and keep the pages linked without the rustdoc JSON.
+    let mut reference = |link: BrokenLink| {
+        let name = link.reference.strip_prefix('`')?.strip_suffix('`')?;
+        let url = api?.resolve(name);
+        if url.is_none() {
+            unresolved.push(name.to_string());
+        }
+        Some((CowStr::from(url?), CowStr::Borrowed("")))
+    };
+    let events = image::figures(code::group(admonition::rewrite(
+        MarkParser::new_with_broken_link_callback(raw, options, Some(&mut reference)).collect(),
+        &config.admonitions,
+    )));
+    let parser = events.into_iter().filter_map(|event| match event {
+        // Buffer the whole heading, its id and text are only known at the end.
+        event if heading.is_some() && !matches!(event, Event::End(Tag::Heading(..))) => {
+            heading.as_mut().unwrap().push(event);
+            None
+        }
+        Event::Start(Tag::Heading(..)) => {
+            heading = Some(Vec::new());
+            None
+        }
+        Event::End(Tag::Heading(level, id, ref classes)) => {
+            let inner = heading.take().unwrap_or_default();
+            let text = inner
+                .iter()
+                .filter_map(|e| match e {
+                    Event::Text(t) | Event::Code(t) => Some(t.as_ref()),
+                    _ => None,
+                })
+                .collect::<String>();
+            let name = text.trim();
+            let id = slugger.slug(name, id);
+            let mut content = String::new();
+            push_html(&mut content, inner.into_iter());
+
+            let heading = templates
+                .render(
+                    "heading.html",
+                    context! {
+                        level => level as u8,
+                        id,
+                        classes,
+                        content => Value::from_safe_string(content),
+                    },
+                )
+                .unwrap_or_else(|e| {
+                    error.get_or_insert(e);
+                    String::new()
+                });
+
+            if level == HeadingLevel::H1 && meta.title.is_none() {
+                meta.title = Some(name.to_owned());
+            }
+
+            if level >= HeadingLevel::H2 && level as u8 <= config.outline {
+                toc.push((level as u8, name.to_owned(), id.clone()));
+            }
+
+            search.heading(level, name, Some(&id));
+            anchors.insert(id);
+
+            Some(Event::Html(CowStr::from(heading)))
+        }
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(_lang))) => {
+            code = Some(String::new());
+            None
+        }
+        Event::End(Tag::CodeBlock(CodeBlockKind::Fenced(ref info))) => {
+            let info = code::Info::parse(info);
+            let lang = registry::resolve(&info.lang);
+            let (code, marks) = info.strip_diff(&code.take().unwrap());
+            search.code_block(&code);
+            let html = code::highlight(languages, lang, &code);
+            let div = info.render(templates, &html, &marks).unwrap_or_else(|e| {
+                error.get_or_insert(e);
+                String::new()
+            });
+            Some(Event::Html(CowStr::from(div)))
+        }
+        Event::Text(ref text) => {
+            if code.is_some() {
+                code.replace(text.to_string());
+                None
+            } else if img.is_some() {
+                img.replace(text.to_string());
+                None
+            } else {
+                search.text(text);
+                if let Some(paragraph) = paragraph.as_mut() {
+                    paragraph.push_str(text);
+                }
+                Some(event)
+            }
+        }
+        Event::Code(ref text) => {
+            search.text(text);
+            if let Some(paragraph) = paragraph.as_mut() {
+                paragraph.push_str(text);
+            }
+            search.code(text);
+            let mut code = String::new();
+            code.push_str("<code>");
+            // code.push_str(text);
+            let mut inline_html = String::new();
+            push_html(&mut inline_html, MarkParser::new(text));
+            code.push_str(
+                inline_html
+                    .trim_start_matches("<p>")
+                    .trim_end_matches("</p>")
+                    .trim(),
+            );
+            code.push_str("</code>");
+            Some(Event::Html(CowStr::from(code)))
+        }
+        Event::Start(Tag::Image(_, _, alt)) => {
+            img = Some(alt.to_string());
+            None
+        }
+        Event::End(Tag::Image(kind, src, title)) => {
+            match kind {
+                LinkType::Inline | LinkType::Autolink => {
+                    let mut src = src.to_string();
+                    let alt = img.take().unwrap_or_else(|| title.to_string());
+                    let mut image = None;
+
+                    if let Some(file) = src.strip_prefix("../") {
+                        // Local images are sized from their source, and linked under
+                        // their fingerprinted name.
+                        image = Image::load(&config.dir.join(&navs.2).join(file)).ok();
+                        src = format!(
+                            "/docs/{}/{}/{}",
+                            config.locale,
+                            navs.2,
+                            assets.map_or(file, |assets| assets.resolve(file))
+                        );
+                    }
+
+                    let html = image::html(image.as_ref(), &src, &alt, &title);
+                    images.push(src);
+
+                    Some(Event::Html(CowStr::from(html)))
+                }
+                _ => None,
+            }
+        }
+        Event::Start(Tag::Link(_, ref dest, _)) => {
+            links.push(dest.to_string());
+            Some(event)
+        }
+        Event::Start(Tag::Paragraph) if meta.description.is_none() => {
+            paragraph = Some(String::new());
+            Some(event)
+        }
+        Event::End(Tag::Paragraph) if paragraph.is_some() => {
+            let text = paragraph.take().unwrap_or_default();
+            if !text.trim().is_empty() {
+                meta.description = Some(summary(&text));
+            }
+            search.text(" ");
+            Some(event)
+        }
+        Event::SoftBreak
+        | Event::HardBreak
+        | Event::End(Tag::Paragraph | Tag::Item | Tag::TableCell) => {
+            search.text(" ");
+            if let Some(paragraph) = paragraph.as_mut() {
+                paragraph.push(' ');
+            }
+            Some(event)
+        }
+        _ => Some(event),
+    });
+
+    let mut body = String::new();
+    push_html(&mut body, parser);
+    if let Some(e) = error {
+        return Err(e.into());
+    }
+    let outline = outline(toc);
+    let (prev, next, _) = navs;
+
+    let mut html = String::new();
+    html.push_str("<article class='flex-1'>");
+    html.push_str(&body);
+
+    let link = |(title, link): &(String, String)| context! { title, link };
+    html.push_str(&templates.render(
+        "page-nav.html",
+        context! {
+            prev => prev.as_ref().map(link),
+            next => next.as_ref().map(link),
+            labels => context! { prev => config.prev, next => config.next },
+        },
+    )?);
+
+    html.push_str("</article>");
+
+    html.push_str(&templates.render(
+        "outline.html",
+        context! { title => config.title, items => &outline },
+    )?);
+
+    Ok(Document {
+        html,
+        body,
+        outline,
+        prev,
+        next,
+        meta,
+        sections: search.finish(),
+        anchors,
+        links,
+        images,
+        unresolved,
+    })
+}
+
+/// A heading of the outline, with the deeper headings following it.
+#[derive(Debug, Serialize)]
+pub struct Outline {
+    pub level: u8,
+    pub text: String,
+    pub id: String,
+    pub children: Vec<Outline>,
+}
+
+/// Nests the headings of the outline, a deeper heading under the previous one.
+fn outline(toc: Vec<(u8, String, String)>) -> Vec<Outline> {
+    // The lists being filled, and the level of their headings.
+    let mut lists = vec![(0, Vec::<Outline>::new())];
+    for (level, text, id) in toc {
+        let (top, items) = lists.last_mut().unwrap();
+        if items.is_empty() {
+            *top = level;
+        } else if level > *top {
+            lists.push((level, Vec::new()));
+        } else {
+            while lists.len() > 1 && level <= lists[lists.len() - 2].0 {
+                close(&mut lists);
+            }
+            if lists.len() > 1 {
+                lists.last_mut().unwrap().0 = level;
+            }
+        }
+        lists.last_mut().unwrap().1.push(Outline {
+            level,
+            text,
+            id,
+            children: Vec::new(),
+        });
+    }
+    while lists.len() > 1 {
+        close(&mut lists);
+    }
+    lists.pop().unwrap().1
+}
+
+/// Moves the innermost list into the last heading of its parent.
+fn close(lists: &mut Vec<(u8, Vec<Outline>)>) {
+    let (_, items) = lists.pop().unwrap();
+    if let Some(parent) = lists.last_mut().unwrap().1.last_mut() {
+        parent.children = items;
+    }
+}
+
+/// Collapses the whitespace of `text` and shortens it to a meta description.
+fn summary(text: &str) -> String {
+    const MAX: usize = 160;
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX {
+        return text;
+    }
+    let mut summary = text.chars().take(MAX - 1).collect::<String>();
+    // Cut at a word, unless the text has no spaces like Chinese.
+    if let Some(i) = summary.rfind(' ').filter(|i| *i > MAX / 2) {
+        summary.truncate(i);
+    }
+    summary.push('…');
+    summary
+}
+
+fn find_prev_and_next(
+    toc: &[Section],
+    lang: &str,
+    version: &str,
+    dir: &str,
+    current: &str,
+) -> Navs {
+    let mut prev = None;
+    let mut next = None;
+
+    if let Some((pos, section)) = toc.iter().enumerate().find(|(_, e)| e.prefix == dir) {
+        if let Some(index) =
+            section
+                .items
+                .iter()
+                .enumerate()
+                .find_map(|(i, e)| if e.1 == current { Some(i) } else { None })
+        {
+            if index > 0 {
+                prev = section.items.get(index - 1).cloned().map(|(name, link)| {
+                    (
+                        name,
+                        format!("{}/{}/{}/{}", lang, version, section.prefix, link),
+                    )
+                });
+            } else if pos > 0 {
+                prev = toc.get(pos - 1).and_then(|section| {
+                    section.items.last().cloned().map(|(name, link)| {
+                        (
+                            name,
+                            format!("{}/{}/{}/{}", lang, version, section.prefix, link),
+                        )
+                    })
+                });
+            }
+
+            if index + 1 < section.items.len() {
+                next = section.items.get(index + 1).cloned().map(|(name, link)| {
+                    (
+                        name,
+                        format!("{}/{}/{}/{}", lang, version, section.prefix, link),
+                    )
+                });
+            } else if pos + 1 < toc.len() {
+                next = toc.get(pos + 1).and_then(|section| {
+                    section.items.first().cloned().map(|(name, link)| {
+                        (
+                            name,
+                            format!("{}/{}/{}/{}", lang, version, section.prefix, link),
+                        )
+                    })
+                });
+            }
+        }
+    }
+
+    (prev, next, version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        let mut config =
+            toml::from_str::<Config>("title = 'On this page'\nprev = 'Previous'\nnext = 'Next'")
+                .unwrap();
+        config.locale = "en".to_string();
+        config.dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/en");
+        config
+    }
+
+    fn render(raw: &str) -> Document {
+        let site = Site::builder().build().unwrap();
+        site.render(&config(), "0.4.x", raw).unwrap()
+    }
+
+    fn toc() -> Vec<Section> {
+        let section = |text: &str, prefix: &str, items: &[(&str, &str)]| Section {
+            text: text.to_string(),
+            prefix: prefix.to_string(),
+            items: items
+                .iter()
+                .map(|(name, link)| (name.to_string(), link.to_string()))
+                .collect(),
+        };
+        vec![
+            section(
+                "Guide",
+                "guide",
+                &[
+                    ("Introduction", "introduction"),
+                    ("Quick Start", "quick-start"),
+                ],
+            ),
+            section("Concepts", "concepts", &[("Routing", "routing")]),
+        ]
+    }
+
+    #[test]
+    fn headings() {
+        let document = render(
+            "# Routing\n\nRoutes map paths to handlers.\n\n## Paths\n\n### Params {#params}\n\n\
+             #### Wildcards\n\n## Paths\n\n#### Query {#params}\n",
+        );
+
+        assert_eq!(document.meta.title.as_deref(), Some("Routing"));
+        assert_eq!(
+            document.meta.description.as_deref(),
+            Some("Routes map paths to handlers.")
+        );
+        assert!(document.html.contains("<h2 id='paths'"));
+        assert!(document
+            .html
+            .contains("<a class=anchor href='#params'>#</a>Params</h3>"));
+        assert_eq!(
+            document.anchors.iter().collect::<Vec<_>>(),
+            [
+                "params",
+                "params-1",
+                "paths",
+                "paths-1",
+                "routing",
+                "wildcards"
+            ]
+        );
+
+        // `h4` is deeper than the default outline.
+        let outline = &document.outline;
+        assert_eq!(outline.len(), 2);
+        assert_eq!((outline[0].level, outline[0].id.as_str()), (2, "paths"));
+        assert_eq!(outline[0].children.len(), 1);
+        assert_eq!(outline[0].children[0].text, "Params");
+        assert!(outline[0].children[0].children.is_empty());
+        assert_eq!(outline[1].id, "paths-1");
+        assert!(document.html.ends_with("</ul></nav>"));
+    }
+
+    #[test]
+    fn images() {
+        let document = render(
+            "Inline ![Logo](https://viz.rs/logo.svg) image.\n\n\
+             ![Flow](../images/flow.png \"Request flow\")\n",
+        );
+
+        assert!(document
+            .html
+            .contains("<p>Inline <img alt='Logo' src='https://viz.rs/logo.svg' loading='lazy'"));
+        // A titled image alone in its paragraph is a figure, local images are
+        // served from the version, sized and offered at narrower widths.
+        assert!(document.body.contains(
+            "<figure><picture><source type='image/avif' \
+             srcset='/docs/en/0.4.x/images/flow-480w.avif 480w, \
+             /docs/en/0.4.x/images/flow-640w.avif 640w' \
+             sizes='(min-width: 960px) 960px, 100vw' /><source type='image/webp' \
+             srcset='/docs/en/0.4.x/images/flow-480w.webp 480w, \
+             /docs/en/0.4.x/images/flow-640w.webp 640w' \
+             sizes='(min-width: 960px) 960px, 100vw' /><img alt='Flow' \
+             src='/docs/en/0.4.x/images/flow.png' width='640' height='320' loading='lazy' \
+             decoding='async' /></picture><figcaption>Request flow</figcaption></figure>"
+        ));
+        assert!(!document.body.contains("<p><figure>"));
+        assert_eq!(
+            document.images,
+            ["https://viz.rs/logo.svg", "/docs/en/0.4.x/images/flow.png"]
+        );
+    }
+
+    #[test]
+    fn references() {
+        let document = render(
+            "Call [`extract`] on the request.\n\n\
+             [`extract`]: https://docs.rs/viz/0.4.x/viz/trait.RequestExt.html#tymethod.extract\n",
+        );
+
+        // Defined references keep their target, with or without the rustdoc JSON.
+        assert!(document.html.contains(
+            "<a href=\"https://docs.rs/viz/0.4.x/viz/trait.RequestExt.html#tymethod.extract\">\
+             <code>extract"
+        ));
+        assert!(document.unresolved.is_empty());
+    }
+
+    #[test]
+    fn code_blocks() {
+        let document = render(
+            "```rust title=\"src/main.rs\" showLineNumbers\nfn main() {}\n```\n\n\
+             ```toml tab=\"Cargo\"\n[dependencies]\n```\n\n\
+             ```bash tab=\"Shell\"\ncargo add viz\n```\n\n\
+             ```console\n$ cargo run\nlistening on http://127.0.0.1:3000\n```\n",
+        );
+
+        assert!(document
+            .html
+            .contains("<div class='code-title'>src/main.rs</div><div class='code line-numbers'>"));
+        assert!(document.html.contains("<button class='i-lucide-copy"));
+        // Consecutive tabs are grouped, the first one shown.
+        assert!(document.html.contains(
+            "<div class='code-group'><div class='tabs'><button class='tab active' \
+             data-tab='Cargo'>Cargo</button><button class='tab' data-tab='Shell'>"
+        ));
+        assert!(document
+            .html
+            .contains("<div class='tab-panel' data-tab='Shell'><div class='code'>"));
+        // Only the commands of a session are highlighted.
+        assert!(document
+            .html
+            .contains("<pre class='language-shell-session'><code><span class='prompt'>$ </span>"));
+        assert!(document
+            .html
+            .contains("<span class='output'>listening on http://127.0.0.1:3000</span>"));
+        assert!(document.outline.is_empty());
+    }
+
+    #[test]
+    fn diff_blocks() {
+        let info = code::Info::parse("rust diff");
+        let (code, marks) = info.strip_diff(" fn main() {\n-    a();\n+    b();\n     c();\n }\n");
+        assert_eq!(code, "fn main() {\n    a();\n    b();\n    c();\n}\n");
+        assert_eq!(marks, [None, Some("remove"), Some("add"), None, None]);
+
+        // Not a unified diff, the lines are kept as written.
+        let raw = "fn main() {\n-    a();\n+    b();\n    c();\n}\n";
+        let (code, marks) = info.strip_diff(raw);
+        assert_eq!(code, raw);
+        assert!(marks.is_empty());
+    }
+
+    #[test]
+    fn navigation() {
+        let toc = toc();
+        let link = |name: &str, link: &str| Some((name.to_string(), link.to_string()));
+
+        let (prev, next, version) =
+            find_prev_and_next(&toc, "en", "0.4.x", "guide", "introduction");
+        assert_eq!(prev, None);
+        assert_eq!(next, link("Quick Start", "en/0.4.x/guide/quick-start"));
+        assert_eq!(version, "0.4.x");
+
+        // Across sections.
+        let (prev, next, _) = find_prev_and_next(&toc, "en", "0.4.x", "guide", "quick-start");
+        assert_eq!(prev, link("Introduction", "en/0.4.x/guide/introduction"));
+        assert_eq!(next, link("Routing", "en/0.4.x/concepts/routing"));
+
+        let (prev, next, _) = find_prev_and_next(&toc, "en", "0.4.x", "concepts", "routing");
+        assert_eq!(prev, link("Quick Start", "en/0.4.x/guide/quick-start"));
+        assert_eq!(next, None);
+
+        let navs = find_prev_and_next(&toc, "en", "0.4.x", "guide", "quick-start");
+        let document = parse(
+            &config(),
+            &registry::languages().unwrap(),
+            &Templates::load(None).unwrap(),
+            None,
+            None,
+            navs,
+            FrontMatter::default(),
+            "# Quick Start\n",
+        )
+        .unwrap();
+        assert!(document.html.contains(
+            "<a class='prev-link transition-colors hover:op100 op61.8' \
+             href='/en/0.4.x/guide/introduction'>"
+        ));
+        assert!(document
+            .html
+            .contains("Next <i class='block i-lucide-chevron-right w-3 h-3'></i></span>"));
+        assert!(document
+            .html
+            .contains("<span class='title'>Routing</span></a>"));
+        assert_eq!(
+            document.prev,
+            link("Introduction", "en/0.4.x/guide/introduction")
+        );
+        assert!(!document.body.contains("page-nav"));
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+/// Logical paths of the files of a `<locale>/<version>` or `<locale>/blog` output
+/// directory to their fingerprinted names, e.g. `toc.json` to `toc.1a2b3c4d.json`.
+///
+/// Written as `manifest.json` by builds with `--hash`, whose other files can then
+/// be cached forever. The manifest itself keeps its name and must be revalidated.
+#[derive(Debug, Default, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Manifest {
+    files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Loads the manifest of `dir`, empty for builds without `--hash`.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join("manifest.json");
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Records the fingerprinted name of `path` for `hash` and returns it.
+    pub fn insert(&mut self, path: &str, hash: u64) -> String {
+        let name = match path.rsplit_once('.') {
+            Some((stem, extension)) if !extension.contains('/') => {
+                format!("{stem}.{:08x}.{extension}", hash as u32)
+            }
+            _ => format!("{path}.{:08x}", hash as u32),
+        };
+        self.files.insert(path.to_string(), name.clone());
+        name
+    }
+
+    /// The fingerprinted name of `path`, or `path` when it has none.
+    pub fn resolve<'a>(&'a self, path: &'a str) -> &'a str {
+        self.files.get(path).map_or(path, String::as_str)
+    }
+
+    /// Writes `manifest.json` into `dir`, then deletes the fingerprinted files of
+    /// previous builds that it no longer maps, along with their image variants.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let fp = dir.join("manifest.json");
+        fs::write(&fp, serde_json::to_string(&self.files)?)?;
+        println!("{:?}", fp.canonicalize()?);
+        self.prune(dir)
+    }
+
+    /// Deletes `manifest.json` and the fingerprinted files left in `dir` by builds
+    /// with `--hash`, which the app would keep resolving otherwise.
+    pub fn remove(dir: &Path) -> Result<()> {
+        let fp = dir.join("manifest.json");
+        if fp.is_file() {
+            fs::remove_file(fp)?;
+        }
+        Self::default().prune(dir)
+    }
+
+    /// Deletes the fingerprinted files of `dir` that are not mapped.
+    fn prune(&self, dir: &Path) -> Result<()> {
+        let current = self
+            .files
+            .values()
+            .filter_map(|name| fingerprinted(name))
+            .collect::<BTreeSet<_>>();
+        for entry in WalkDir::new(dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let name = entry
+                .path()
+                .strip_prefix(dir)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            if fingerprinted(&name).is_some_and(|stem| !current.contains(stem)) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The path of a fingerprinted file up to its hash, `toc.1a2b3c4d` of
+/// `toc.1a2b3c4d.json` or of the variant `toc.1a2b3c4d-480w.webp`.
+fn fingerprinted(name: &str) -> Option<&str> {
+    let file = name.rfind('/').map_or(0, |i| i + 1);
+    name[file..].match_indices('.').find_map(|(i, _)| {
+        let start = file + i + 1;
+        let hash = name.get(start..start + 8)?;
+        let end = name[start + 8..].chars().next();
+        (hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+            && matches!(end, None | Some('.' | '-')))
+        .then(|| &name[..start + 8])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// An empty output directory, unique to `name`.
+    fn dist(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gen-manifest-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("guide")).unwrap();
+        fs::create_dir_all(dir.join("images")).unwrap();
+        dir
+    }
+
+    fn write(dir: &Path, names: &[&str]) {
+        for name in names {
+            fs::write(dir.join(name), name).unwrap();
+        }
+    }
+
+    fn files(dir: &Path) -> Vec<String> {
+        let mut files = WalkDir::new(dir)
+            .into_iter()
+            .map(Result::unwrap)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| {
+                let name = entry.path().strip_prefix(dir).unwrap().to_string_lossy();
+                name.replace('\\', "/")
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    /// Files of any build, none of them fingerprinted.
+    const PLAIN: [&str; 7] = [
+        "404.html",
+        "meta.json",
+        "search.json",
+        "guide/introduction.html",
+        "guide/introduction.png",
+        "images/TFB-7ce481b2-49ec-4a4d.png",
+        "images/release.0.4.x.png",
+    ];
+
+    #[test]
+    fn fingerprints() {
+        assert_eq!(fingerprinted("toc.1a2b3c4d.json"), Some("toc.1a2b3c4d"));
+        assert_eq!(
+            fingerprinted("images/flow.1a2b3c4d-480w.avif"),
+            Some("images/flow.1a2b3c4d")
+        );
+        assert_eq!(
+            fingerprinted("guide/intro.1a2b3c4d"),
+            Some("guide/intro.1a2b3c4d")
+        );
+        assert_eq!(fingerprinted("1a2b3c4d/toc.json"), None);
+        assert_eq!(fingerprinted("toc.1A2B3C4D.json"), None);
+        assert_eq!(fingerprinted("toc.1a2b3c4.json"), None);
+        assert_eq!(fingerprinted("toc.1a2b3c4d5.json"), None);
+        for name in PLAIN {
+            assert_eq!(fingerprinted(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn save_prunes_previous_builds() {
+        let dir = dist("save");
+        write(&dir, &PLAIN);
+        write(
+            &dir,
+            &[
+                "toc.00000000.json",
+                "toc.11111111.json",
+                "guide/introduction.22222222.html",
+                "guide/introduction.22222222.json",
+                "guide/introduction.33333333.html",
+                "images/flow.44444444.png",
+                "images/flow.44444444-480w.avif",
+                "images/flow.55555555-480w.webp",
+            ],
+        );
+
+        let mut manifest = Manifest::default();
+        assert_eq!(
+            manifest.insert("toc.json", 0x1111_1111),
+            "toc.11111111.json"
+        );
+        manifest.insert("guide/introduction.html", 0x2222_2222);
+        manifest.insert("images/flow.png", 0x4444_4444);
+        manifest.save(&dir).unwrap();
+
+        let mut expected = PLAIN.to_vec();
+        expected.extend([
+            "guide/introduction.22222222.html",
+            "guide/introduction.22222222.json",
+            "images/flow.44444444-480w.avif",
+            "images/flow.44444444.png",
+            "manifest.json",
+            "toc.11111111.json",
+        ]);
+        expected.sort_unstable();
+        assert_eq!(files(&dir), expected);
+        assert_eq!(
+            Manifest::load(&dir).unwrap().resolve("toc.json"),
+            "toc.11111111.json"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn remove_clears_hashed_builds() {
+        let dir = dist("remove");
+        write(&dir, &PLAIN);
+        write(
+            &dir,
+            &[
+                "manifest.json",
+                "toc.11111111.json",
+                "guide/introduction.22222222.html",
+                "images/flow.44444444-960w.webp",
+            ],
+        );
+
+        Manifest::remove(&dir).unwrap();
+        let mut expected = PLAIN.to_vec();
+        expected.sort_unstable();
+        assert_eq!(files(&dir), expected);
+        assert!(Manifest::load(&dir).unwrap().files.is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
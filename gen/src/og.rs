@@ -0,0 +1,172 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use pulldown_cmark::escape::escape_html;
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{Options, Tree},
+};
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 630;
+const MARGIN: f32 = 80.0;
+
+/// Title font size, and the lines it may take before being cut.
+const TITLE_SIZE: f32 = 64.0;
+const TITLE_LINES: usize = 3;
+
+/// Inter like the site, fonts with CJK glyphs, then common fallbacks.
+const FONTS: &str = "Inter, 'Noto Sans SC', 'Noto Sans CJK SC', 'Noto Sans TC', \
+                     'Noto Sans CJK TC', 'Source Han Sans SC', 'PingFang SC', \
+                     'Microsoft YaHei', Helvetica, Arial, 'DejaVu Sans', sans-serif";
+
+/// Fonts fetched by `cargo make fonts`, the Noto Sans SC and TC subsets.
+const FONTS_DIR: &str = "gen/fonts";
+
+/// The logo drawn in the corner, that of the app.
+const LOGO: &str = "app/logo.svg";
+
+/// Renders the Open Graph image of a page, a 1200×630 PNG.
+///
+/// Fonts are those of `gen/fonts`, then the system ones. Titles with CJK characters
+/// are refused without a font covering them, instead of rendering blank boxes.
+pub struct Og {
+    options: Options<'static>,
+    logo: Tree,
+    /// A font has CJK glyphs.
+    cjk: bool,
+}
+
+impl Og {
+    /// Loads the fonts and the logo of the repository at `root`.
+    pub fn new(root: &Path) -> Result<Self> {
+        let mut options = Options::default();
+        let fonts = options.fontdb_mut();
+        let dir = root.join(FONTS_DIR);
+        if dir.is_dir() {
+            fonts.load_fonts_dir(dir);
+        }
+        fonts.load_system_fonts();
+        let cjk = fonts.faces().any(|face| {
+            fonts
+                .with_face_data(face.id, |data, index| {
+                    ttf_parser::Face::parse(data, index).ok()?.glyph_index('中')
+                })
+                .flatten()
+                .is_some()
+        });
+
+        let path = root.join(LOGO);
+        let svg =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let logo = Tree::from_str(&svg, &options).context("invalid app/logo.svg")?;
+
+        Ok(Self { options, logo, cjk })
+    }
+
+    /// Renders a page titled `title` of the `section` of the docs, with a `badge`
+    /// such as the version.
+    pub fn render(&self, title: &str, section: &str, badge: &str) -> Result<Vec<u8>> {
+        if !self.cjk
+            && [title, section, badge]
+                .iter()
+                .any(|text| text.chars().any(wide))
+        {
+            bail!("no CJK font to render `{title}`, run `cargo make fonts`");
+        }
+
+        let mut svg = String::new();
+        svg.push_str(&format!(
+            "<svg xmlns='http://www.w3.org/2000/svg' width='{WIDTH}' height='{HEIGHT}' \
+             font-family=\"{FONTS}\"><rect width='100%' height='100%' fill='#ffffff'/>\
+             <rect width='100%' height='8' fill='#ca8a04'/>\
+             <text x='{}' y='141' font-size='44'><tspan font-weight='600'>V</tspan>iz</text>",
+            MARGIN + 112.0
+        ));
+
+        svg.push_str(&format!(
+            "<text x='{MARGIN}' y='280' font-size='32' fill='#ca8a04'>"
+        ));
+        escape_html(&mut svg, section)?;
+        svg.push_str("</text>");
+
+        svg.push_str(&format!(
+            "<text y='360' font-size='{TITLE_SIZE}' font-weight='700' fill='#171717'>"
+        ));
+        for (i, line) in wrap(title).iter().enumerate() {
+            let dy = if i == 0 { 0.0 } else { TITLE_SIZE * 1.25 };
+            svg.push_str(&format!("<tspan x='{MARGIN}' dy='{dy}'>"));
+            escape_html(&mut svg, line)?;
+            svg.push_str("</tspan>");
+        }
+        svg.push_str("</text>");
+
+        // The badge is sized from the width of its text.
+        let badge_width = width(badge) * 28.0 + 48.0;
+        svg.push_str(&format!(
+            "<rect x='{MARGIN}' y='518' rx='24' width='{badge_width}' height='48' fill='#fef9c3'/>\
+             <text x='{}' y='552' font-size='28' fill='#a16207'>",
+            MARGIN + 24.0
+        ));
+        escape_html(&mut svg, badge)?;
+        svg.push_str(&format!(
+            "</text><text x='{}' y='552' font-size='28' text-anchor='end' fill='#737373'>viz.rs</text></svg>",
+            WIDTH as f32 - MARGIN
+        ));
+
+        let tree = Tree::from_str(&svg, &self.options)?;
+        let mut pixmap = Pixmap::new(WIDTH, HEIGHT).ok_or_else(|| anyhow!("empty image"))?;
+        resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+
+        let scale = 96.0 / self.logo.size().width();
+        resvg::render(
+            &self.logo,
+            Transform::from_row(scale, 0.0, 0.0, scale, MARGIN, 72.0),
+            &mut pixmap.as_mut(),
+        );
+
+        Ok(pixmap.encode_png()?)
+    }
+}
+
+/// CJK characters, square and breakable anywhere.
+fn wide(c: char) -> bool {
+    matches!(c, '\u{2e80}'..='\u{9fff}' | '\u{ac00}'..='\u{d7af}' | '\u{ff00}'..='\u{ffef}')
+}
+
+/// Estimated width of `text` in ems.
+fn width(text: &str) -> f32 {
+    text.chars()
+        .map(|c| match c {
+            c if wide(c) => 1.0,
+            'A'..='Z' | 'm' | 'w' => 0.68,
+            'i' | 'l' | 'j' | 't' | 'f' | 'r' | '.' | ',' | ' ' | '\'' => 0.3,
+            _ => 0.56,
+        })
+        .sum()
+}
+
+/// Breaks the title into the lines that fit, at spaces or between CJK characters.
+fn wrap(title: &str) -> Vec<String> {
+    let max = (WIDTH as f32 - MARGIN * 2.0) / TITLE_SIZE;
+
+    let mut lines = vec![String::new()];
+    for word in title.split_inclusive(|c: char| c == ' ' || wide(c)) {
+        let line = lines.last_mut().unwrap();
+        if !line.is_empty() && width(line) + width(word.trim_end()) > max {
+            lines.push(word.trim_start().to_string());
+        } else {
+            line.push_str(word);
+        }
+    }
+
+    if lines.len() > TITLE_LINES {
+        lines.truncate(TITLE_LINES);
+        let last = lines.last_mut().unwrap();
+        while !last.is_empty() && width(last) + 1.0 > max {
+            last.pop();
+        }
+        last.push('…');
+    }
+    lines
+}
@@ -0,0 +1,318 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use minijinja::context;
+use pulldown_cmark::escape::escape_html;
+
+use crate::{
+    front_matter::FrontMatter, load_config, locales, manifest::Manifest, sitemap,
+    templates::Templates, Config, Section,
+};
+
+/// A page of the app and the markup shown until the app is mounted.
+struct Route {
+    /// Path of the route, without leading `/`.
+    path: String,
+    title: String,
+    description: Option<String>,
+    sidebar: Option<String>,
+    article: String,
+    /// Path of the Open Graph image.
+    image: Option<String>,
+}
+
+/// Writes `<route>.html` for every page of the docs and of the blog in a trunk
+/// build, so static hosts serve the markup of the page before the wasm app is
+/// loaded. Hosts serve `/en/0.4.x/guide/introduction` from
+/// `en/0.4.x/guide/introduction.html` without redirecting to a trailing slash.
+///
+/// The pages are the `index.html` shell with the navbar, the sidebar and the
+/// article inside `<div id="prerender">`, which the app removes when it mounts.
+/// `404.html` serves routes without a page, and `sitemap.xml` and `robots.txt`
+/// list the pages of the repository at `root`.
+pub fn prerender(root: &Path, templates: &Templates, dist: &Path, base_url: &str) -> Result<()> {
+    let shell = fs::read_to_string(dist.join("index.html"))
+        .with_context(|| format!("missing trunk build in {}", dist.display()))?;
+    // The name of the site, from the `Viz.rs | ...` title of the shell.
+    let site = shell
+        .split_once("<title>")
+        .and_then(|(_, title)| title.split(['|', '<']).next())
+        .map(str::trim)
+        .unwrap_or_default();
+    let docs = dist.join("docs");
+
+    let mut routes = Vec::new();
+    for locale in dirs(&docs)? {
+        for version in dirs(&docs.join(&locale))? {
+            let dir = docs.join(&locale).join(&version);
+            if version == "blog" {
+                routes.extend(blog(&load_config(root, &locale)?.0, &dir)?);
+            } else if dir.join("meta.json").is_file() {
+                routes.extend(pages(templates, &dir, &locale, &version)?);
+            }
+        }
+    }
+
+    for route in &routes {
+        let fp = dist.join(&route.path).with_extension("html");
+        fs::create_dir_all(fp.parent().unwrap())?;
+        fs::write(
+            &fp,
+            page(
+                templates,
+                &shell,
+                site,
+                route,
+                base_url.trim_end_matches('/'),
+            )?,
+        )?;
+    }
+    println!("prerendered {} pages into {}", routes.len(), dist.display());
+
+    fs::write(dist.join("404.html"), not_found(templates, &shell, site)?)?;
+    sitemap::write(root, dist, base_url, &locales(root)?)?;
+
+    Ok(())
+}
+
+/// Pages of a `<locale>/<version>` directory, in the order of its toc.
+fn pages(templates: &Templates, root: &Path, locale: &str, version: &str) -> Result<Vec<Route>> {
+    let manifest = Manifest::load(root)?;
+    let toc = serde_json::from_str::<Vec<Section>>(&fs::read_to_string(
+        root.join(manifest.resolve("toc.json")),
+    )?)?;
+    let metas = metas(&root.join("meta.json"))?;
+
+    let mut routes = Vec::new();
+    for section in &toc {
+        for (name, link) in &section.items {
+            let key = format!("{}/{link}", section.prefix);
+            let Ok(article) =
+                fs::read_to_string(root.join(manifest.resolve(&format!("{key}.html"))))
+            else {
+                continue;
+            };
+            let path = format!("{locale}/{version}/{key}");
+            let meta = metas.get(&key);
+            routes.push(Route {
+                title: meta
+                    .and_then(|meta| meta.title.clone())
+                    .unwrap_or_else(|| name.clone()),
+                description: meta.and_then(|meta| meta.description.clone()),
+                sidebar: Some(templates.render(
+                    "sidebar.html",
+                    context! { toc, base => format!("{locale}/{version}"), current => path },
+                )?),
+                article,
+                image: root
+                    .join(&key)
+                    .with_extension("png")
+                    .is_file()
+                    .then(|| format!("docs/{locale}/{version}/{key}.png")),
+                path,
+            });
+        }
+    }
+
+    Ok(routes)
+}
+
+/// The listing pages and the posts of the blog of `config.locale`.
+fn blog(config: &Config, root: &Path) -> Result<Vec<Route>> {
+    let locale = &config.locale;
+    let manifest = Manifest::load(root)?;
+    let metas = metas(&root.join("meta.json"))?;
+
+    let mut routes = Vec::new();
+    let mut listing = |path: String, file: &str| -> Result<()> {
+        routes.push(Route {
+            path,
+            title: config.blog.title.clone(),
+            description: Some(config.blog.description.clone()),
+            sidebar: None,
+            article: fs::read_to_string(root.join(manifest.resolve(file)))?,
+            image: None,
+        });
+        Ok(())
+    };
+
+    listing(format!("{locale}/blog"), "index.html")?;
+    for n in 2.. {
+        let file = format!("page/{n}.html");
+        if !root.join(manifest.resolve(&file)).is_file() {
+            break;
+        }
+        listing(format!("{locale}/blog/page/{n}"), &file)?;
+    }
+
+    for (slug, meta) in metas {
+        routes.push(Route {
+            path: format!("{locale}/blog/{slug}"),
+            title: meta.title.unwrap_or_else(|| slug.clone()),
+            description: meta.description,
+            sidebar: None,
+            article: fs::read_to_string(root.join(manifest.resolve(&format!("{slug}.html"))))?,
+            image: root
+                .join(&slug)
+                .with_extension("png")
+                .is_file()
+                .then(|| format!("docs/{locale}/blog/{slug}.png")),
+        });
+    }
+
+    Ok(routes)
+}
+
+fn page(
+    templates: &Templates,
+    shell: &str,
+    site: &str,
+    route: &Route,
+    base_url: &str,
+) -> Result<String> {
+    let locale = route.path.split('/').next().unwrap_or_default();
+    let mut html = shell.to_string();
+
+    let mut title = String::new();
+    escape_html(&mut title, &route.title).unwrap();
+    title.push_str(" | ");
+    title.push_str(site);
+    replace(&mut html, "<title>", "</title>", &title);
+    replace(&mut html, "<html lang=\"", "\"", locale);
+    let mut description = String::new();
+    if let Some(text) = &route.description {
+        escape_html(&mut description, text).unwrap();
+        replace(
+            &mut html,
+            "<meta name=\"description\" content=\"",
+            "\"",
+            &description,
+        );
+    }
+
+    // The tags the app's `Document` sets, for crawlers that do not run the app.
+    let url = format!("{base_url}/{}", route.path);
+    let image = route
+        .image
+        .as_ref()
+        .map(|image| format!("{base_url}/{image}"));
+    let mut head = String::new();
+    head.push_str("<link rel=\"canonical\" href=\"");
+    escape_html(&mut head, &url).unwrap();
+    head.push_str("\" />");
+    for (property, content) in [
+        ("og:type", "article"),
+        ("og:site_name", site),
+        ("og:title", &title),
+        ("og:description", &description),
+        ("og:url", &url),
+        ("og:locale", &locale.replace('-', "_")),
+    ]
+    .into_iter()
+    .chain(image.as_deref().map(|image| ("og:image", image)))
+    {
+        head.push_str("<meta property=\"");
+        head.push_str(property);
+        head.push_str("\" content=\"");
+        head.push_str(content);
+        head.push_str("\" />");
+    }
+    for (name, content) in [
+        (
+            "twitter:card",
+            if image.is_some() {
+                "summary_large_image"
+            } else {
+                "summary"
+            },
+        ),
+        ("twitter:title", &title),
+        ("twitter:description", &description),
+    ] {
+        head.push_str("<meta name=\"");
+        head.push_str(name);
+        head.push_str("\" content=\"");
+        head.push_str(content);
+        head.push_str("\" />");
+    }
+    head.push_str("</head>");
+    html = html.replacen("</head>", &head, 1);
+
+    let body = body(templates, locale, route.sidebar.as_deref(), &route.article)?;
+    Ok(html.replacen("<body>", &body, 1))
+}
+
+/// The shell with the app's `NotFound` page, kept out of search indexes.
+fn not_found(templates: &Templates, shell: &str, site: &str) -> Result<String> {
+    let mut html = shell.replacen(
+        "</head>",
+        "<meta name=\"robots\" content=\"noindex\" /></head>",
+        1,
+    );
+    replace(&mut html, "<title>", "</title>", &format!("404 | {site}"));
+    let locale = html
+        .split_once("<html lang=\"")
+        .and_then(|(_, lang)| lang.split_once('"'))
+        .map(|(lang, _)| lang.to_string())
+        .unwrap_or_default();
+    let article = templates.render("not-found.html", ())?;
+    let body = body(templates, &locale, None, &article)?;
+    Ok(html.replacen("<body>", &body, 1))
+}
+
+/// The markup of the app inside `<div id="prerender">`, replacing `<body>`.
+fn body(
+    templates: &Templates,
+    locale: &str,
+    sidebar: Option<&str>,
+    article: &str,
+) -> Result<String> {
+    let mut body = String::new();
+    body.push_str("<body><div id=\"prerender\"><div class=\"tracking-0.2px\">");
+    body.push_str(&templates.render("navbar.html", context! { locale })?);
+    body.push_str("<div class=\"page-container pt-4.375rem");
+    if sidebar.is_some() {
+        body.push_str(" opened");
+    }
+    body.push_str("\">");
+    if let Some(sidebar) = sidebar {
+        body.push_str(sidebar);
+    }
+    body.push_str("<main id=\"page\" class=\"flex flex-row flex-1 py-5\"><div class=\"flex flex-row flex-1\">");
+    body.push_str(article);
+    body.push_str("</div></main></div></div></div>");
+    Ok(body)
+}
+
+/// Replaces the text between the first `open` and the following `close`.
+fn replace(html: &mut String, open: &str, close: &str, value: &str) {
+    let Some(start) = html.find(open).map(|i| i + open.len()) else {
+        return;
+    };
+    if let Some(end) = html[start..].find(close) {
+        html.replace_range(start..start + end, value);
+    }
+}
+
+fn metas(path: &Path) -> Result<BTreeMap<String, FrontMatter>> {
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Names of the subdirectories of `dir`, sorted.
+fn dirs(dir: &Path) -> Result<Vec<String>> {
+    let mut dirs = Vec::new();
+    if !dir.is_dir() {
+        return Ok(dirs);
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            dirs.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
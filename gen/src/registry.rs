@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use highlighting::{HighlightConfiguration, Languages};
+use tree_sitter::Language;
+
+/// A tree-sitter grammar and its queries under `gen/queries/<name>`.
+pub struct Grammar {
+    pub name: &'static str,
+    /// Other names accepted in the info string of a fenced code block.
+    pub aliases: &'static [&'static str],
+    pub language: fn() -> Language,
+    pub highlights: &'static str,
+    pub injections: &'static str,
+    pub locals: &'static str,
+}
+
+macro_rules! query {
+    ($name:literal, $file:literal) => {
+        include_str!(concat!("../queries/", $name, "/", $file, ".scm"))
+    };
+}
+
+/// Every highlighted language, adding one takes an entry here and its queries.
+pub const GRAMMARS: &[Grammar] = &[
+    Grammar {
+        name: "bash",
+        aliases: &["sh", "shell", "zsh"],
+        language: tree_sitter_bash::language,
+        highlights: query!("bash", "highlights"),
+        injections: query!("bash", "injections"),
+        locals: query!("bash", "locals"),
+    },
+    Grammar {
+        name: "c",
+        aliases: &["h"],
+        language: tree_sitter_c::language,
+        highlights: query!("c", "highlights"),
+        injections: query!("c", "injections"),
+        locals: "",
+    },
+    Grammar {
+        name: "css",
+        aliases: &[],
+        language: tree_sitter_css::language,
+        highlights: query!("css", "highlights"),
+        injections: "",
+        locals: "",
+    },
+    Grammar {
+        name: "dockerfile",
+        aliases: &["docker", "Dockerfile"],
+        language: tree_sitter_dockerfile::language,
+        highlights: query!("dockerfile", "highlights"),
+        injections: "",
+        locals: "",
+    },
+    Grammar {
+        name: "html",
+        aliases: &["htm"],
+        language: tree_sitter_html::language,
+        highlights: query!("html", "highlights"),
+        injections: query!("html", "injections"),
+        locals: query!("html", "locals"),
+    },
+    Grammar {
+        name: "javascript",
+        aliases: &["js", "mjs"],
+        language: tree_sitter_javascript::language,
+        highlights: query!("javascript", "highlights"),
+        injections: query!("javascript", "injections"),
+        locals: query!("javascript", "locals"),
+    },
+    Grammar {
+        name: "json",
+        aliases: &[],
+        language: tree_sitter_json::language,
+        highlights: query!("json", "highlights"),
+        injections: "",
+        locals: query!("json", "locals"),
+    },
+    Grammar {
+        name: "jsx",
+        aliases: &[],
+        language: tree_sitter_javascript::language,
+        highlights: query!("jsx", "highlights"),
+        injections: query!("jsx", "injections"),
+        locals: query!("jsx", "locals"),
+    },
+    Grammar {
+        name: "markdown",
+        aliases: &["md"],
+        language: tree_sitter_md::language,
+        highlights: query!("markdown", "highlights"),
+        injections: query!("markdown", "injections"),
+        locals: "",
+    },
+    Grammar {
+        name: "markdown_inline",
+        aliases: &[],
+        language: tree_sitter_md::inline_language,
+        highlights: query!("markdown_inline", "highlights"),
+        injections: query!("markdown_inline", "injections"),
+        locals: "",
+    },
+    Grammar {
+        name: "rust",
+        aliases: &["rs"],
+        language: tree_sitter_rust::language,
+        highlights: query!("rust", "highlights"),
+        injections: query!("rust", "injections"),
+        locals: query!("rust", "locals"),
+    },
+    Grammar {
+        name: "sql",
+        aliases: &[],
+        language: tree_sitter_sequel::language,
+        highlights: query!("sql", "highlights"),
+        injections: "",
+        locals: "",
+    },
+    Grammar {
+        name: "toml",
+        aliases: &[],
+        language: tree_sitter_toml::language,
+        highlights: query!("toml", "highlights"),
+        injections: query!("toml", "injections"),
+        locals: query!("toml", "locals"),
+    },
+    Grammar {
+        name: "yaml",
+        aliases: &["yml"],
+        language: tree_sitter_yaml::language,
+        highlights: query!("yaml", "highlights"),
+        injections: "",
+        locals: "",
+    },
+    Grammar {
+        name: "zig",
+        aliases: &[],
+        language: tree_sitter_zig::language,
+        highlights: query!("zig", "highlights"),
+        injections: query!("zig", "injections"),
+        locals: "",
+    },
+];
+
+/// Compiles the queries of every grammar.
+pub fn languages() -> Result<Languages> {
+    let mut languages = Languages::new();
+
+    for grammar in GRAMMARS {
+        languages.insert(
+            grammar.name,
+            HighlightConfiguration::new(
+                (grammar.language)(),
+                grammar.highlights,
+                grammar.injections,
+                grammar.locals,
+            )
+            .with_context(|| format!("invalid queries of `{}`", grammar.name))?,
+        );
+    }
+
+    Ok(languages)
+}
+
+/// A terminal transcript, only its commands are highlighted with the `bash` grammar.
+pub const SESSION: &str = "shell-session";
+
+const SESSION_ALIASES: &[&str] = &["console", "sh-session", "shellsession"];
+
+/// Returns the registered name of `lang`, unknown languages are returned as is.
+pub fn resolve(lang: &str) -> &str {
+    if SESSION_ALIASES.contains(&lang) {
+        return SESSION;
+    }
+    GRAMMARS
+        .iter()
+        .find(|g| g.name == lang || g.aliases.contains(&lang))
+        .map_or(lang, |g| g.name)
+}
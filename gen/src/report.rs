@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeSet,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::Result;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::{front_matter, locales, versions, Section};
+
+/// The locale every translation is compared against.
+const SOURCE: &str = "en";
+
+/// Translation status of a `<locale>/<version>` directory.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub locale: String,
+    pub version: String,
+    pub total: usize,
+    pub translated: usize,
+    /// `en` pages without a translation.
+    pub missing: Vec<String>,
+    /// `en` toc entries that are absent from the locale's toc.
+    pub missing_from_toc: Vec<String>,
+    /// Translated pages that are not listed in the locale's toc.
+    pub not_in_toc: Vec<String>,
+    /// Translated pages whose `en` source changed afterwards.
+    pub stale: Vec<Stale>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stale {
+    pub page: String,
+    pub reason: String,
+}
+
+/// Compares every locale's `toc.json` and pages of `root` against `en`.
+pub fn report(root: &Path, json: bool) -> Result<()> {
+    let mut reports = Vec::new();
+
+    for version in versions(root, SOURCE)? {
+        let source = root.join(SOURCE).join(&version);
+        let source_pages = pages(&source)?;
+        let source_toc = toc(&source)?;
+
+        for locale in locales(root)?.into_iter().filter(|l| l != SOURCE) {
+            let dir = root.join(&locale).join(&version);
+            let translated_pages = pages(&dir)?;
+            let translated_toc = toc(&dir)?;
+
+            let mut report = Report {
+                locale,
+                version: version.clone(),
+                total: source_pages.len(),
+                translated: source_pages.intersection(&translated_pages).count(),
+                missing: source_pages
+                    .difference(&translated_pages)
+                    .cloned()
+                    .collect(),
+                missing_from_toc: source_toc.difference(&translated_toc).cloned().collect(),
+                not_in_toc: translated_pages
+                    .difference(&translated_toc)
+                    .cloned()
+                    .collect(),
+                stale: Vec::new(),
+            };
+
+            for page in source_pages.intersection(&translated_pages) {
+                let file = PathBuf::from(format!("{page}.md"));
+                if let Some(reason) = staleness(root, &source.join(&file), &dir.join(&file))? {
+                    report.stale.push(Stale {
+                        page: page.clone(),
+                        reason,
+                    });
+                }
+            }
+
+            reports.push(report);
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
+    }
+
+    for report in &reports {
+        println!(
+            "{}/{}: {}/{} pages translated ({}%)",
+            report.locale,
+            report.version,
+            report.translated,
+            report.total,
+            report.translated * 100 / report.total.max(1)
+        );
+        for page in &report.missing {
+            println!("  missing       {page}");
+        }
+        for page in &report.missing_from_toc {
+            println!("  not in toc    {page} (listed in {SOURCE})");
+        }
+        for page in &report.not_in_toc {
+            println!("  not in toc    {page}");
+        }
+        for Stale { page, reason } in &report.stale {
+            println!("  stale         {page} ({reason})");
+        }
+    }
+
+    Ok(())
+}
+
+/// Pages of a version directory as `<section>/<page>`, drafts included.
+fn pages(root: &Path) -> Result<BTreeSet<String>> {
+    let mut pages = BTreeSet::new();
+    if !root.is_dir() {
+        return Ok(pages);
+    }
+    for entry in WalkDir::new(root) {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension() == Some(OsStr::new("md")) {
+            let page = path.strip_prefix(root)?.with_extension("");
+            pages.insert(page.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(pages)
+}
+
+fn toc(root: &Path) -> Result<BTreeSet<String>> {
+    let path = root.join("toc.json");
+    if !path.is_file() {
+        return Ok(BTreeSet::new());
+    }
+    let toc = serde_json::from_str::<Vec<Section>>(&fs::read_to_string(path)?)?;
+    Ok(toc
+        .iter()
+        .flat_map(|section| {
+            section
+                .items
+                .iter()
+                .map(|(_, link)| format!("{}/{link}", section.prefix))
+        })
+        .collect())
+}
+
+/// A translation recording a `source-hash` is stale when the `en` page no longer
+/// has that hash, otherwise when `en` was committed after the translation.
+fn staleness(root: &Path, source: &Path, translation: &Path) -> Result<Option<String>> {
+    let (meta, _) = front_matter::split(&fs::read_to_string(translation)?)?;
+
+    if let Some(recorded) = meta.source_hash {
+        return Ok(git(root, &["hash-object", &source.to_string_lossy()])
+            .filter(|current| !current.starts_with(&recorded))
+            .map(|current| format!("source hash {recorded} is now {}", &current[..7])));
+    }
+
+    let committed = |path: &Path| {
+        git(
+            root,
+            &["log", "-1", "--format=%ct", "--", &path.to_string_lossy()],
+        )
+        .and_then(|time| time.parse::<u64>().ok())
+    };
+
+    Ok(match (committed(source), committed(translation)) {
+        (Some(s), Some(t)) if s > t => Some(format!(
+            "{SOURCE} committed {} days later",
+            (s - t).div_ceil(86_400)
+        )),
+        _ => None,
+    })
+}
+
+/// Runs git in `dir`, returning its trimmed output if it succeeds with one.
+pub fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let stdout = stdout.trim();
+    (!stdout.is_empty()).then(|| stdout.to_string())
+}
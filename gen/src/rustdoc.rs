@@ -0,0 +1,186 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::cache;
+
+/// The public items of viz, read from the rustdoc JSON at `gen/rustdoc/<version>.json`.
+///
+/// Generated by `cargo make rustdoc`, which runs
+/// `cargo +nightly rustdoc -p viz --all-features -- -Z unstable-options --output-format json`.
+#[derive(Debug)]
+pub struct Api {
+    /// Docs version of the viz crates, e.g. `0.4.x`.
+    version: String,
+    /// Hash of the JSON file, pages are rebuilt when it changes.
+    pub hash: u64,
+    items: Vec<Item>,
+}
+
+#[derive(Debug)]
+struct Item {
+    krate: String,
+    path: Vec<String>,
+    kind: String,
+}
+
+#[derive(Deserialize)]
+struct Crate {
+    root: serde_json::Value,
+    paths: HashMap<String, Summary>,
+    external_crates: HashMap<String, External>,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    crate_id: u32,
+    path: Vec<String>,
+    kind: String,
+}
+
+#[derive(Deserialize)]
+struct External {
+    name: String,
+}
+
+impl Api {
+    /// Loads the API of a docs version from `gen/rustdoc` in `root`, `None` if no
+    /// rustdoc JSON is provided.
+    pub fn load(root: &Path, version: &str) -> Result<Option<Self>> {
+        let path = root.join("gen/rustdoc").join(format!("{version}.json"));
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let raw = fs::read_to_string(&path)?;
+        let krate = serde_json::from_str::<Crate>(&raw)
+            .with_context(|| format!("invalid rustdoc JSON {}", path.display()))?;
+        let root = krate.root.to_string().trim_matches('"').to_string();
+        let local = krate
+            .paths
+            .get(&root)
+            .and_then(|s| s.path.first())
+            .cloned()
+            .unwrap_or_else(|| "viz".to_string());
+
+        let items = krate
+            .paths
+            .into_values()
+            .filter_map(|summary| {
+                let krate = if summary.crate_id == 0 {
+                    local.clone()
+                } else {
+                    krate
+                        .external_crates
+                        .get(&summary.crate_id.to_string())?
+                        .name
+                        .clone()
+                };
+                Some(Item {
+                    krate,
+                    path: summary.path,
+                    kind: summary.kind,
+                })
+            })
+            .collect();
+
+        Ok(Some(Self {
+            version: version.to_string(),
+            hash: cache::hash(&raw),
+            items,
+        }))
+    }
+
+    /// Resolves a reference such as `` `Router` ``, `` `Params\<T\>` ``,
+    /// `` `serve::File` `` or `` `Router::route` `` to a docs.rs url.
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        let name = name.replace('\\', "");
+        let name = name.split('<').next().unwrap_or_default().trim();
+        let segments = name.split("::").collect::<Vec<_>>();
+
+        // Exact names first, then case-insensitive ones like `handler`.
+        for exact in [true, false] {
+            if let Some(item) = self.find(&segments, exact) {
+                return Some(self.url(item));
+            }
+        }
+
+        // `Type::method`
+        let (method, ty) = segments.split_last()?;
+        let item = self
+            .find(ty, true)
+            .filter(|item| matches!(item.kind.as_str(), "struct" | "enum" | "trait" | "union"))?;
+        Some(format!("{}#method.{method}", self.url(item)))
+    }
+
+    /// Finds the item whose path ends with `segments`, items of the viz crates first.
+    fn find(&self, segments: &[&str], exact: bool) -> Option<&Item> {
+        if segments.is_empty() {
+            return None;
+        }
+
+        self.items
+            .iter()
+            .filter(|item| item.path.len() >= segments.len())
+            .filter(|item| {
+                item.path[item.path.len() - segments.len()..]
+                    .iter()
+                    .zip(segments)
+                    .all(|(a, b)| {
+                        if exact {
+                            a == b
+                        } else {
+                            a.eq_ignore_ascii_case(b)
+                        }
+                    })
+            })
+            .min_by_key(|item| {
+                (
+                    !item.krate.starts_with("viz"),
+                    item.path.len(),
+                    item.path.clone(),
+                )
+            })
+    }
+
+    fn url(&self, item: &Item) -> String {
+        let version = if item.krate.starts_with("viz") {
+            self.version.as_str()
+        } else {
+            "latest"
+        };
+        let (name, modules) = item.path.split_last().unwrap();
+
+        let mut url = format!(
+            "https://docs.rs/{}/{version}/",
+            item.krate.replace('_', "-")
+        );
+        if item.kind == "module" {
+            for segment in &item.path {
+                url.push_str(segment);
+                url.push('/');
+            }
+            url.push_str("index.html");
+            return url;
+        }
+
+        for segment in modules {
+            url.push_str(segment);
+            url.push('/');
+        }
+        let kind = match item.kind.as_str() {
+            "function" => "fn",
+            "type_alias" | "typedef" => "type",
+            "proc_attribute" => "attr",
+            "proc_derive" => "derive",
+            "trait_alias" => "traitalias",
+            kind => kind,
+        };
+        url.push_str(kind);
+        url.push('.');
+        url.push_str(name);
+        url.push_str(".html");
+        url
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeSet;
+
+use pulldown_cmark::HeadingLevel;
+use serde::{Deserialize, Serialize};
+
+const KEYWORDS: [&str; 12] = [
+    "Self", "Some", "None", "Ok", "Err", "Option", "Result", "String", "Vec", "Box", "Fn", "FnMut",
+];
+
+/// A searchable page, written into `search.json` next to `toc.json`.
+///
+/// Field names are shortened to keep the index small, the wasm app loads it lazily.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Page {
+    #[serde(rename = "t")]
+    pub title: String,
+    #[serde(rename = "p")]
+    pub path: String,
+    #[serde(rename = "s")]
+    pub sections: Vec<Section>,
+}
+
+/// A heading of a page and the content below it, up to the next heading.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Section {
+    #[serde(rename = "h")]
+    pub heading: String,
+    #[serde(rename = "a")]
+    pub anchor: String,
+    #[serde(rename = "l")]
+    pub level: u8,
+    #[serde(rename = "x", default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    #[serde(rename = "c", default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub code: BTreeSet<String>,
+}
+
+/// Collects sections while `parse()` walks the markdown events.
+#[derive(Debug, Default)]
+pub struct Collector {
+    sections: Vec<Section>,
+}
+
+impl Collector {
+    /// Starts a new section.
+    ///
+    /// Headings without an anchor deep-link to the closest previous one.
+    pub fn heading(&mut self, level: HeadingLevel, name: &str, anchor: Option<&str>) {
+        let anchor = anchor.map_or_else(
+            || {
+                self.sections
+                    .last()
+                    .map(|s| s.anchor.clone())
+                    .unwrap_or_default()
+            },
+            ToString::to_string,
+        );
+        self.sections.push(Section {
+            heading: name.to_string(),
+            anchor,
+            level: level as u8,
+            ..Section::default()
+        });
+    }
+
+    /// Appends body text, whitespace is collapsed in `finish`.
+    pub fn text(&mut self, text: &str) {
+        self.current().text.push_str(text);
+    }
+
+    /// Records an inline code span as an identifier.
+    pub fn code(&mut self, text: &str) {
+        let text = text.trim();
+        if text.len() <= 40
+            && text.contains(char::is_alphanumeric)
+            && !text.contains(char::is_whitespace)
+        {
+            self.current().code.insert(text.to_string());
+        }
+    }
+
+    /// Records the type-like identifiers of a fenced code block.
+    pub fn code_block(&mut self, code: &str) {
+        let section = self.current();
+        code.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .filter(|w| w.len() > 1 && w.starts_with(|c: char| c.is_ascii_uppercase()))
+            .filter(|w| !KEYWORDS.contains(w))
+            .for_each(|w| {
+                section.code.insert(w.to_string());
+            });
+    }
+
+    pub fn finish(self) -> Vec<Section> {
+        self.sections
+            .into_iter()
+            .map(|mut section| {
+                section.text = section
+                    .text
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                section
+            })
+            .filter(|s| !s.heading.is_empty() || !s.text.is_empty() || !s.code.is_empty())
+            .collect()
+    }
+
+    fn current(&mut self) -> &mut Section {
+        if self.sections.is_empty() {
+            self.sections.push(Section::default());
+        }
+        self.sections.last_mut().unwrap()
+    }
+}
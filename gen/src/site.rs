@@ -0,0 +1,666 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+};
+
+use anyhow::{anyhow, bail, Result};
+use globset::GlobBuilder;
+use highlighting::Languages;
+use walkdir::WalkDir;
+
+use crate::{
+    blog,
+    cache::{self, Cache},
+    check, doctest, find_prev_and_next,
+    front_matter::{self, FrontMatter},
+    image::{self, Image},
+    json, load_config, locales,
+    manifest::Manifest,
+    og::Og,
+    parse, prerender, read_toc, registry, report,
+    rustdoc::Api,
+    search, sitemap,
+    templates::Templates,
+    versions, Config, Document, Section,
+};
+
+/// Options of a [`Site`], created by [`Site::builder`].
+#[derive(Debug)]
+pub struct Builder {
+    root: PathBuf,
+    templates: Option<PathBuf>,
+    outputs: Outputs,
+    force: bool,
+    base_url: String,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            templates: None,
+            outputs: Outputs::default(),
+            force: false,
+            base_url: "https://viz.rs".to_string(),
+        }
+    }
+}
+
+impl Builder {
+    /// Root of the repository, with the content, `gen/locales`, `gen/rustdoc`,
+    /// `gen/fonts` and `app`. Defaults to the current directory.
+    pub fn root(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.root = dir.into();
+        self
+    }
+
+    /// Theme directory whose templates override those of `gen/templates`.
+    pub fn templates(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.templates = Some(dir.into());
+        self
+    }
+
+    /// Fingerprints the names of `toc.json`, the pages and the images, and maps
+    /// them in `manifest.json`.
+    pub fn hash(mut self, hash: bool) -> Self {
+        self.outputs.hash = hash;
+        self
+    }
+
+    /// Writes `<page>.json` next to every page.
+    pub fn json(mut self, json: bool) -> Self {
+        self.outputs.json = json;
+        self
+    }
+
+    /// Ignores the build cache and regenerates every file.
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Site url of the blog feeds, the prerendered pages, `sitemap.xml` and `robots.txt`.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Loads the grammars and the templates.
+    pub fn build(self) -> Result<Site> {
+        Ok(Site {
+            root: self.root,
+            languages: registry::languages()?,
+            templates: Templates::load(self.templates.as_deref())?,
+            og: OnceLock::new(),
+            apis: Mutex::default(),
+            outputs: self.outputs,
+            force: self.force,
+            base_url: self.base_url,
+        })
+    }
+}
+
+/// The docs and the blogs of every locale, rendered with the same grammars and
+/// templates.
+pub struct Site {
+    root: PathBuf,
+    languages: Languages,
+    templates: Templates,
+    /// Loaded by the first build, rendering a single page does not need fonts.
+    og: OnceLock<Og>,
+    /// The API of each version, read once from its rustdoc JSON.
+    apis: Mutex<HashMap<String, Option<Arc<Api>>>>,
+    outputs: Outputs,
+    force: bool,
+    base_url: String,
+}
+
+impl Site {
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Locales that have both a `gen/locales/<locale>.toml` config and a content
+    /// directory.
+    pub fn locales(&self) -> Result<Vec<String>> {
+        locales(&self.root)
+    }
+
+    /// Reads the config of `locale`, `gen/locales/<locale>.toml`.
+    pub fn config(&self, locale: &str) -> Result<Config> {
+        load_config(&self.root, locale).map(|(config, _)| config)
+    }
+
+    /// Reads the sections of a version of the docs, `<locale>/<version>/toc.json`.
+    pub fn toc(&self, locale: &str, version: &str) -> Result<Vec<Section>> {
+        read_toc(&self.root.join(locale).join(version))
+    }
+
+    /// Renders the markdown `raw`, front matter included, as a page of `version`
+    /// in `config.locale`, without page navigation.
+    pub fn render(&self, config: &Config, version: &str, raw: &str) -> Result<Document> {
+        let (meta, body) = front_matter::split(raw)?;
+        parse(
+            config,
+            &self.languages,
+            &self.templates,
+            self.api(version)?.as_deref(),
+            None,
+            (None, None, version.to_string()),
+            meta,
+            body,
+        )
+    }
+
+    /// Renders every version and the blog of each locale into its directory, the
+    /// versions in parallel, returning the summary of each locale.
+    pub fn build(&self, locales: &[(String, PathBuf)]) -> Result<BTreeMap<String, Summary>> {
+        // Loaded once before the threads need it.
+        let og = self.og()?;
+        let mut targets = Vec::new();
+        let mut blogs = Vec::new();
+        for (locale, dist) in locales {
+            let (config, raw_config) = load_config(&self.root, locale)?;
+            blogs.push((config.clone(), dist.clone()));
+            let fingerprint = cache::fingerprint(&raw_config, &self.root.join("gen/queries"))?;
+            for version in versions(&self.root, &config.locale)? {
+                targets.push(Target {
+                    config: config.clone(),
+                    fingerprint,
+                    version,
+                    dist: dist.clone(),
+                    outputs: self.outputs,
+                });
+            }
+        }
+
+        let summaries = thread::scope(|s| {
+            targets
+                .iter()
+                .map(|target| s.spawn(|| build(self, target)))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| Summary {
+                        errors: vec![anyhow!("gen thread panicked")],
+                        ..Summary::default()
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut locales = BTreeMap::<String, Summary>::new();
+        for (target, summary) in targets.iter().zip(summaries) {
+            let total = locales.entry(target.config.locale.clone()).or_default();
+            total.written += summary.written;
+            total.cached += summary.cached;
+            total.errors.extend(summary.errors);
+        }
+        for (config, dist) in &blogs {
+            let total = locales.entry(config.locale.clone()).or_default();
+            match blog::build(
+                &self.root,
+                &self.languages,
+                &self.templates,
+                og,
+                config,
+                dist,
+                &self.base_url,
+                self.outputs,
+            ) {
+                Ok(written) => total.written += written,
+                Err(e) => total
+                    .errors
+                    .push(e.context(format!("{}/blog", config.locale))),
+            }
+        }
+
+        Ok(locales)
+    }
+
+    /// Writes the html of every docs and blog route, `404.html`, `sitemap.xml` and
+    /// `robots.txt` into the trunk build `dist`.
+    pub fn prerender(&self, dist: &Path) -> Result<()> {
+        prerender::prerender(&self.root, &self.templates, dist, &self.base_url)
+    }
+
+    /// Checks page links, `#anchors` and images of every locale and version.
+    pub fn check_links(&self) -> Result<()> {
+        check::check_links(&self.root, &self.languages, &self.templates)
+    }
+
+    /// Reports missing, untracked and outdated translations against `en`, as JSON
+    /// with `json`.
+    pub fn report(&self, json: bool) -> Result<()> {
+        report::report(&self.root, json)
+    }
+
+    /// Compiles the rust code blocks of `locale` with `cargo check`, against the
+    /// checkout of viz `viz`, or one checkout per version under it, if any.
+    pub fn compile_check(&self, locale: &str, viz: Option<&Path>) -> Result<()> {
+        doctest::compile_check(&self.root, locale, viz)
+    }
+
+    fn og(&self) -> Result<&Og> {
+        if let Some(og) = self.og.get() {
+            return Ok(og);
+        }
+        let og = Og::new(&self.root)?;
+        Ok(self.og.get_or_init(|| og))
+    }
+
+    fn api(&self, version: &str) -> Result<Option<Arc<Api>>> {
+        let mut apis = self.apis.lock().unwrap();
+        if let Some(api) = apis.get(version) {
+            return Ok(api.clone());
+        }
+        let api = Api::load(&self.root, version)?.map(Arc::new);
+        apis.insert(version.to_string(), api.clone());
+        Ok(api)
+    }
+
+    /// `path` relative to the root, as shown in messages and documents.
+    fn relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+}
+
+/// A `<locale>/<version>` directory to render.
+#[derive(Debug)]
+struct Target {
+    config: Config,
+    fingerprint: u64,
+    version: String,
+    dist: PathBuf,
+    outputs: Outputs,
+}
+
+/// Optional outputs of a build.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Outputs {
+    /// Fingerprinted names, mapped in `manifest.json`.
+    pub hash: bool,
+    /// A `<page>.json` document next to every page.
+    pub json: bool,
+}
+
+/// Files of a locale written and left from the cache, and its errors.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub written: usize,
+    pub cached: usize,
+    pub errors: Vec<anyhow::Error>,
+}
+
+fn build(site: &Site, target: &Target) -> Summary {
+    let mut build = match Build::new(site, target) {
+        Ok(build) => build,
+        Err(e) => {
+            return Summary {
+                errors: vec![e],
+                ..Summary::default()
+            }
+        }
+    };
+
+    let root = &target.config.dir;
+    let glob = match GlobBuilder::new("**/*.{json,md,png,jpg,jpeg,gif,svg,webp}")
+        .literal_separator(true)
+        .build()
+    {
+        Ok(glob) => glob.compile_matcher(),
+        Err(e) => {
+            build.summary.errors.push(e.into());
+            return build.summary;
+        }
+    };
+
+    let iter = WalkDir::new(root.join(&target.version)).sort_by(|a, b| {
+        let at = a.file_type().is_file();
+        let bt = b.file_type().is_file();
+
+        if at && !bt {
+            Ordering::Less
+        } else if at && bt {
+            Ordering::Equal
+        } else {
+            Ordering::Greater
+        }
+    });
+
+    for entry in iter {
+        match entry {
+            Ok(entry) if glob.is_match(entry.path()) => {
+                if let Err(e) = build.file(root, entry.path()) {
+                    build
+                        .summary
+                        .errors
+                        .push(e.context(site.relative(entry.path()).display().to_string()));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => build.summary.errors.push(e.into()),
+        }
+    }
+
+    build.finish()
+}
+
+/// Renders the files of a single `<locale>/<version>` directory.
+struct Build<'a> {
+    site: &'a Site,
+    target: &'a Target,
+    api: Option<Arc<Api>>,
+    minify_cfg: minify_html::Cfg,
+    minify_cfg_js: minify_html::Cfg,
+    cache: Cache,
+    /// Fingerprinted names of the outputs, with `--hash`.
+    manifest: Option<Manifest>,
+    /// Hash of the images, the pages linking their fingerprinted names.
+    assets: u64,
+    toc: Option<Vec<Section>>,
+    metas: BTreeMap<String, FrontMatter>,
+    pages: Vec<search::Page>,
+    summary: Summary,
+}
+
+impl<'a> Build<'a> {
+    fn new(site: &'a Site, target: &'a Target) -> Result<Self> {
+        let mut minify_cfg = minify_html::Cfg::new();
+        minify_cfg.keep_closing_tags = true;
+
+        let mut minify_cfg_js = minify_html::Cfg::new();
+        minify_cfg_js.minify_js = true;
+
+        let cache = Cache::load(
+            site.root.join("target/gen-cache").join(format!(
+                "{}.json",
+                cache::hash(&(&target.config.locale, &target.version, &target.dist))
+            )),
+            site.force,
+        );
+
+        // Images are fingerprinted first, the pages are rendered with their names.
+        let mut manifest = None;
+        let mut assets = 0;
+        if target.outputs.hash {
+            let root = target.config.dir.join(&target.version);
+            let mut images = Manifest::default();
+            for entry in WalkDir::new(&root).sort_by_file_name() {
+                let entry = entry?;
+                let path = entry.path();
+                if matches!(path.extension().and_then(OsStr::to_str), Some(e) if image::EXTENSIONS.contains(&e))
+                {
+                    let raw = fs::read(path)?;
+                    images.insert(
+                        &path.strip_prefix(&root)?.to_string_lossy(),
+                        cache::hash(&(target.fingerprint, &raw)),
+                    );
+                }
+            }
+            assets = cache::hash(&images);
+            manifest = Some(images);
+        }
+
+        let api = site.api(&target.version)?;
+        if api.is_none() {
+            eprintln!(
+                "warning: no gen/rustdoc/{}.json, undefined API references are left unlinked, \
+                 run `cargo make rustdoc`",
+                target.version
+            );
+        }
+
+        Ok(Self {
+            site,
+            target,
+            api,
+            minify_cfg,
+            minify_cfg_js,
+            cache,
+            manifest,
+            assets,
+            toc: None,
+            metas: BTreeMap::new(),
+            pages: Vec::new(),
+            summary: Summary::default(),
+        })
+    }
+
+    fn file(&mut self, root: &Path, path: &Path) -> Result<()> {
+        let file = path.strip_prefix(root)?;
+        let dir = self.target.dist.join(file.parent().unwrap());
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+        }
+        let mut fp = dir.join(file.file_stem().unwrap());
+        let key = file.to_string_lossy().to_string();
+        // Path of the output within the version, the key of the manifest.
+        let name = file.strip_prefix(&self.target.version)?;
+        if matches!(file.extension().and_then(OsStr::to_str), Some(e) if image::EXTENSIONS.contains(&e))
+        {
+            let raw = fs::read(path)?;
+            fp.set_extension(file.extension().unwrap());
+            // The variants depend on gen too.
+            let hash = cache::hash(&(self.target.fingerprint, &raw));
+            self.fingerprint(&mut fp, name, hash);
+            let image = Image::load(path);
+            // A missing variant is regenerated, images that fail to load have none.
+            if self
+                .cache
+                .fresh(&key, hash, &fp)
+                .filter(|_| {
+                    image
+                        .iter()
+                        .flat_map(|image| image.outputs(&fp))
+                        .all(|fp| fp.is_file())
+                })
+                .is_some()
+            {
+                self.summary.cached += 1;
+                return Ok(());
+            }
+            if let Err(error) = image.and_then(|image| image.write(&raw, &fp)) {
+                eprintln!("warning: {error:#}, copied as is");
+                fs::write(&fp, &raw)?;
+            }
+            self.cache.insert(key, cache::Entry::new(hash));
+        } else if matches!(file.extension(), Some(e) if e == "json") {
+            let toc = read_toc(path.parent().unwrap())?;
+            let raw = serde_json::to_string(&toc)?;
+            self.toc.replace(toc);
+            fp.set_extension("json");
+            let hash = cache::hash(&raw);
+            self.fingerprint(&mut fp, name, hash);
+            if self.cache.fresh(&key, hash, &fp).is_some() {
+                self.summary.cached += 1;
+                return Ok(());
+            }
+            fs::write(
+                &fp,
+                minify_html::minify(raw.as_bytes(), &self.minify_cfg_js),
+            )?;
+            self.cache.insert(key, cache::Entry::new(hash));
+        } else {
+            let raw = fs::read_to_string(path)?;
+            let parent = file
+                .parent()
+                .unwrap()
+                .as_os_str()
+                .to_os_string()
+                .into_string()
+                .unwrap();
+            let components: Vec<_> = parent.split('/').collect();
+            let Some(&prefix) = components.get(1) else {
+                bail!("not in a section directory of the version");
+            };
+            let (meta, body) = front_matter::split(&raw)?;
+            if meta.draft {
+                println!("skipping draft {path:?}");
+                return Ok(());
+            }
+            let navs = find_prev_and_next(
+                self.toc
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("missing toc.json"))?,
+                &self.target.config.locale,
+                components[0],
+                prefix,
+                fp.file_name().and_then(OsStr::to_str).unwrap(),
+            );
+            fp.set_extension("html");
+            // The Open Graph image, next to the page under a stable name.
+            let image = fp.with_extension("png");
+            let section = self
+                .toc
+                .iter()
+                .flatten()
+                .find(|section| section.prefix == prefix)
+                .map_or_else(String::new, |section| section.text.clone());
+            // The document of the app with `--json`, dated like in the sitemap.
+            let mut document_json = if self.target.outputs.json {
+                Some((fp.with_extension("json"), sitemap::lastmod(path)?))
+            } else {
+                None
+            };
+            let hash = cache::hash(&(
+                self.target.fingerprint,
+                self.api.as_ref().map(|api| api.hash),
+                self.site.templates.hash,
+                &raw,
+                &navs,
+                &section,
+                self.manifest.as_ref().map(|_| self.assets),
+                document_json.as_ref().map(|(_, date)| date),
+            ));
+            let plain = fp.with_extension("json");
+            self.fingerprint(&mut fp, &name.with_extension("html"), hash);
+            match document_json.as_mut() {
+                Some((json_fp, _)) => self.fingerprint(json_fp, &name.with_extension("json"), hash),
+                None => {
+                    json::remove(&plain)?;
+                    json::remove(&fp.with_extension("json"))?;
+                }
+            }
+            let entry = if let Some(entry) = self
+                .cache
+                .fresh(&key, hash, &fp)
+                .filter(|_| image.is_file())
+                .filter(|_| document_json.iter().all(|(json_fp, _)| json_fp.is_file()))
+            {
+                self.summary.cached += 1;
+                entry
+            } else {
+                let document = parse(
+                    &self.target.config,
+                    &self.site.languages,
+                    &self.site.templates,
+                    self.api.as_deref(),
+                    self.manifest.as_ref(),
+                    navs,
+                    meta,
+                    body,
+                )?;
+                for name in &document.unresolved {
+                    eprintln!(
+                        "warning: {}: `{name}` is not in the API of viz {}",
+                        path.display(),
+                        self.target.version
+                    );
+                }
+                fs::write(
+                    &fp,
+                    minify_html::minify(document.html.as_bytes(), &self.minify_cfg),
+                )?;
+                self.summary.written += 1;
+                println!("{:?}", fp.canonicalize()?);
+                if let Some((json_fp, last_updated)) = &document_json {
+                    let body = minify_html::minify(document.body.as_bytes(), &self.minify_cfg);
+                    let body = String::from_utf8_lossy(&body);
+                    let page = json::Page::new(
+                        &self.target.config,
+                        &document,
+                        &body,
+                        self.site.relative(path),
+                        last_updated,
+                    );
+                    fs::write(json_fp, serde_json::to_string(&page)?)?;
+                    println!("{:?}", json_fp.canonicalize()?);
+                }
+                fs::write(
+                    &image,
+                    self.site.og()?.render(
+                        document
+                            .meta
+                            .title
+                            .as_deref()
+                            .unwrap_or(&file.file_stem().unwrap().to_string_lossy()),
+                        &section,
+                        &format!("v{}", self.target.version),
+                    )?,
+                )?;
+                let entry = cache::Entry {
+                    hash,
+                    meta: document.meta,
+                    sections: document.sections,
+                };
+                self.cache.insert(key, entry.clone());
+                entry
+            };
+            let path = format!("{}/{}", prefix, file.file_stem().unwrap().to_string_lossy());
+            self.pages.push(search::Page {
+                title: entry.meta.title.clone().unwrap_or_default(),
+                path: path.clone(),
+                sections: entry.sections,
+            });
+            self.metas.insert(path, entry.meta);
+            return Ok(());
+        }
+        self.summary.written += 1;
+        println!("{:?}", fp.canonicalize()?);
+        Ok(())
+    }
+
+    /// Renames `fp`, the output of `name`, to its fingerprinted name with `--hash`.
+    fn fingerprint(&mut self, fp: &mut PathBuf, name: &Path, hash: u64) {
+        if let Some(manifest) = self.manifest.as_mut() {
+            let name = manifest.insert(&name.to_string_lossy(), hash);
+            fp.set_file_name(name.rsplit('/').next().unwrap());
+        }
+    }
+
+    /// Writes `meta.json`, `search.json` and `manifest.json` and saves the build cache.
+    fn finish(mut self) -> Summary {
+        if let Err(e) = self.write_indexes() {
+            self.summary.errors.push(e);
+        }
+        if let Err(e) = self.cache.save() {
+            self.summary.errors.push(e);
+        }
+        self.summary
+    }
+
+    fn write_indexes(&self) -> Result<()> {
+        let dist = self.target.dist.join(&self.target.version);
+
+        let fp = dist.join("meta.json");
+        fs::write(&fp, serde_json::to_string(&self.metas)?)?;
+        println!("{:?}", fp.canonicalize()?);
+
+        let fp = dist.join("search.json");
+        fs::write(&fp, serde_json::to_string(&self.pages)?)?;
+        println!("{:?}", fp.canonicalize()?);
+
+        match &self.manifest {
+            Some(manifest) => manifest.save(&dist)?,
+            None => Manifest::remove(&dist)?,
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Result};
+use pulldown_cmark::escape::escape_html;
+
+use crate::{blog, read_toc, report::git, versions};
+
+/// Writes `sitemap.xml` and `robots.txt` into `output`, listing the pages of every
+/// locale and version of `root` with their translations as alternates.
+pub fn write(root: &Path, output: &Path, base_url: &str, locales: &[String]) -> Result<()> {
+    let base_url = base_url.trim_end_matches('/');
+
+    // `<version>/<prefix>/<link>` or `blog/<slug>` to the locales having it and
+    // their last change.
+    let mut pages = BTreeMap::<String, BTreeMap<&str, String>>::new();
+    for locale in locales {
+        for version in versions(root, locale)? {
+            let dir = root.join(locale).join(&version);
+            for section in &read_toc(&dir)? {
+                for (_, link) in &section.items {
+                    let path = dir.join(&section.prefix).join(link).with_extension("md");
+                    if !path.is_file() {
+                        continue;
+                    }
+                    pages
+                        .entry(format!("{version}/{}/{link}", section.prefix))
+                        .or_default()
+                        .insert(locale, lastmod(&path)?);
+                }
+            }
+        }
+
+        // The blog listing changes with its newest post.
+        let posts = blog::posts(&root.join(locale))?;
+        pages
+            .entry("blog".to_string())
+            .or_default()
+            .insert(locale, blog::updated(root, locale, &posts)?);
+        for post in &posts {
+            let path = root
+                .join(locale)
+                .join("blog")
+                .join(&post.slug)
+                .with_extension("md");
+            pages
+                .entry(format!("blog/{}", post.slug))
+                .or_default()
+                .insert(locale, lastmod(&path)?);
+        }
+    }
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(
+        "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\" \
+         xmlns:xhtml=\"http://www.w3.org/1999/xhtml\">\n",
+    );
+    for (page, translations) in &pages {
+        for (locale, lastmod) in translations {
+            xml.push_str("  <url>\n    <loc>");
+            escape_html(&mut xml, &format!("{base_url}/{locale}/{page}"))?;
+            xml.push_str("</loc>\n    <lastmod>");
+            xml.push_str(lastmod);
+            xml.push_str("</lastmod>\n");
+            if translations.len() > 1 {
+                for alternate in translations.keys() {
+                    xml.push_str("    <xhtml:link rel=\"alternate\" hreflang=\"");
+                    escape_html(&mut xml, alternate)?;
+                    xml.push_str("\" href=\"");
+                    escape_html(&mut xml, &format!("{base_url}/{alternate}/{page}"))?;
+                    xml.push_str("\"/>\n");
+                }
+            }
+            xml.push_str("  </url>\n");
+        }
+    }
+    xml.push_str("</urlset>\n");
+
+    fs::create_dir_all(output)?;
+
+    let fp = output.join("sitemap.xml");
+    fs::write(&fp, xml)?;
+    println!("{:?}", fp.canonicalize()?);
+
+    let fp = output.join("robots.txt");
+    fs::write(
+        &fp,
+        format!("User-agent: *\nAllow: /\n\nSitemap: {base_url}/sitemap.xml\n"),
+    )?;
+    println!("{:?}", fp.canonicalize()?);
+
+    Ok(())
+}
+
+/// Date of the last commit touching `path`, or of its modification when uncommitted.
+pub fn lastmod(path: &Path) -> Result<String> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        bail!("not a file {}", path.display());
+    };
+    if let Some(date) = git(
+        dir,
+        &["log", "-1", "--format=%cs", "--", &name.to_string_lossy()],
+    ) {
+        return Ok(date);
+    }
+
+    let secs = fs::metadata(path)?
+        .modified()
+        .unwrap_or_else(|_| SystemTime::now())
+        .duration_since(UNIX_EPOCH)?
+        .as_secs();
+    Ok(date(secs / 86_400))
+}
+
+/// Formats days since the unix epoch as `YYYY-MM-DD`.
+fn date(days: u64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
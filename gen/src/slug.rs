@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+
+use serde::Deserialize;
+
+/// Rules of heading ids, the `[slug]` table of `gen/locales/<locale>.toml`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SlugConfig {
+    /// Lowercases ids, scripts without case such as CJK are kept as is.
+    pub lowercase: bool,
+    /// Replaces whitespace and dashes.
+    pub separator: char,
+}
+
+impl Default for SlugConfig {
+    fn default() -> Self {
+        Self {
+            lowercase: true,
+            separator: '-',
+        }
+    }
+}
+
+/// Produces the unique heading ids of a page.
+#[derive(Debug)]
+pub struct Slugger<'a> {
+    config: &'a SlugConfig,
+    seen: HashSet<String>,
+}
+
+impl<'a> Slugger<'a> {
+    pub fn new(config: &'a SlugConfig) -> Self {
+        Self {
+            config,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Returns the id of a heading, a `{#custom-id}` attribute is kept as is.
+    ///
+    /// Repeated ids, custom ones included, get a numeric suffix: `usage`, `usage-1`,
+    /// `usage-2`.
+    pub fn slug(&mut self, text: &str, custom: Option<&str>) -> String {
+        let mut base = match custom {
+            Some(id) => id.to_string(),
+            None => self.slugify(text),
+        };
+        if base.is_empty() {
+            base.push_str("section");
+        }
+
+        let mut id = base.clone();
+        let mut n = 0;
+        while !self.seen.insert(id.clone()) {
+            n += 1;
+            id = format!("{base}{}{n}", self.config.separator);
+        }
+        id
+    }
+
+    /// Keeps letters and digits of any script, turns whitespace into the separator
+    /// and drops punctuation, including CJK punctuation.
+    pub fn slugify(&self, text: &str) -> String {
+        let mut slug = String::new();
+        let mut separate = false;
+
+        for c in text.chars() {
+            if c.is_alphanumeric() || c == '_' {
+                if separate && !slug.is_empty() {
+                    slug.push(self.config.separator);
+                }
+                separate = false;
+                if self.config.lowercase {
+                    slug.extend(c.to_lowercase());
+                } else {
+                    slug.push(c);
+                }
+            } else if c.is_whitespace() || c == '-' || c == self.config.separator {
+                separate = true;
+            }
+        }
+
+        slug
+    }
+}
@@ -0,0 +1,71 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use minijinja::{escape_formatter, AutoEscape, Environment, Error};
+use pulldown_cmark::escape::escape_html;
+use serde::Serialize;
+
+use crate::cache;
+
+/// The templates of the page chrome, with the context documented at their top.
+const TEMPLATES: [(&str, &str); 7] = [
+    ("heading.html", include_str!("../templates/heading.html")),
+    ("code.html", include_str!("../templates/code.html")),
+    ("page-nav.html", include_str!("../templates/page-nav.html")),
+    ("outline.html", include_str!("../templates/outline.html")),
+    ("navbar.html", include_str!("../templates/navbar.html")),
+    ("sidebar.html", include_str!("../templates/sidebar.html")),
+    (
+        "not-found.html",
+        include_str!("../templates/not-found.html"),
+    ),
+];
+
+/// The minijinja templates of `gen/templates`, each overridable by a file of the
+/// same name in a theme directory given with `--templates`.
+#[derive(Debug)]
+pub struct Templates {
+    env: Environment<'static>,
+    /// Hash of the sources, pages are rebuilt when it changes.
+    pub hash: u64,
+}
+
+impl Templates {
+    pub fn load(theme: Option<&Path>) -> Result<Self> {
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        // Escapes like the rest of the markup.
+        env.set_formatter(|out, state, value| match value.as_str() {
+            Some(text) if state.auto_escape() == AutoEscape::Html && !value.is_safe() => {
+                let mut escaped = String::new();
+                escape_html(&mut escaped, text).unwrap();
+                out.write_str(&escaped).map_err(Error::from)
+            }
+            _ => escape_formatter(out, state, value),
+        });
+
+        let mut sources = Vec::new();
+        for (name, default) in TEMPLATES {
+            let source = match theme
+                .map(|dir| dir.join(name))
+                .filter(|path| path.is_file())
+            {
+                Some(path) => fs::read_to_string(&path)?,
+                None => default.to_string(),
+            };
+            env.add_template_owned(name, source.clone())
+                .with_context(|| format!("invalid template {name}"))?;
+            sources.push(source);
+        }
+
+        Ok(Self {
+            env,
+            hash: cache::hash(&sources),
+        })
+    }
+
+    pub fn render(&self, name: &str, context: impl Serialize) -> Result<String, Error> {
+        self.env.get_template(name)?.render(context)
+    }
+}